@@ -3,8 +3,11 @@
 extern crate proc_macro;
 
 use crate::proc_macro::TokenStream;
-use quote::ToTokens;
-use syn::{self, parse_macro_input, parse_quote, AttributeArgs, FnArg, ItemFn, Stmt};
+use quote::{quote, ToTokens};
+use syn::{
+    self, parse_macro_input, parse_quote, AttributeArgs, Block, FnArg, ItemFn, Lit, Meta,
+    NestedMeta, Pat, Stmt,
+};
 
 #[proc_macro_attribute]
 pub fn tracable_parser(attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -13,54 +16,226 @@ pub fn tracable_parser(attr: TokenStream, item: TokenStream) -> TokenStream {
     impl_tracable_parser(&attr, &item)
 }
 
-fn impl_tracable_parser(_attr: &AttributeArgs, item: &ItemFn) -> TokenStream {
-    let default = impl_tracable_parser_default(&item);
-    let trace = impl_tracable_parser_trace(&item);
+struct TracableAttr {
+    name: Option<String>,
+    fold: bool,
+    silent: bool,
+    memo: bool,
+    guard: bool,
+    dynamic: bool,
+    feature: Option<String>,
+}
 
-    let mut item = item.clone();
+fn parse_tracable_attr(attr: &AttributeArgs) -> TracableAttr {
+    let mut ret = TracableAttr {
+        name: None,
+        fold: false,
+        silent: false,
+        memo: false,
+        guard: false,
+        dynamic: false,
+        feature: None,
+    };
 
-    item.block.stmts.clear();
-    item.block.stmts.push(default);
-    item.block.stmts.push(trace);
+    for arg in attr {
+        match arg {
+            NestedMeta::Meta(Meta::Path(x)) if x.is_ident("fold") => ret.fold = true,
+            NestedMeta::Meta(Meta::Path(x)) if x.is_ident("silent") => ret.silent = true,
+            NestedMeta::Meta(Meta::Path(x)) if x.is_ident("memo") => ret.memo = true,
+            NestedMeta::Meta(Meta::Path(x)) if x.is_ident("guard") => ret.guard = true,
+            NestedMeta::Meta(Meta::Path(x)) if x.is_ident("dynamic") => ret.dynamic = true,
+            NestedMeta::Meta(Meta::NameValue(x)) if x.path.is_ident("name") => match &x.lit {
+                Lit::Str(x) => ret.name = Some(x.value()),
+                _ => panic!("argument `name` of #[tracable_parser] must be string literal"),
+            },
+            NestedMeta::Meta(Meta::NameValue(x)) if x.path.is_ident("cfg") => match &x.lit {
+                Lit::Str(x) => ret.feature = Some(x.value()),
+                _ => panic!("argument `cfg` of #[tracable_parser] must be string literal"),
+            },
+            _ => panic!(
+                "unknown argument of #[tracable_parser]: {}",
+                arg.to_token_stream()
+            ),
+        }
+    }
 
-    item.into_token_stream().into()
+    ret
 }
 
-fn impl_tracable_parser_default(item: &ItemFn) -> Stmt {
-    let body = item.block.as_ref();
-    parse_quote! {
-        #[cfg(not(feature = "trace"))]
-        {
-            #body
+fn impl_tracable_parser(attr: &AttributeArgs, item: &ItemFn) -> TokenStream {
+    let attr = parse_tracable_attr(attr);
+
+    let mut ret = item.clone();
+    ret.block.stmts.clear();
+
+    if attr.dynamic {
+        let dynamic = impl_tracable_parser_dynamic(&attr, item);
+        if attr.feature.is_some() {
+            // `cfg` is respected only if it is specified explicitly
+            // because the crate using `dynamic` may not have `trace` feature.
+            let default = impl_tracable_parser_default(&attr, item);
+            let feature = feature_name(&attr);
+            ret.block.stmts.push(default);
+            ret.block.stmts.push(parse_quote! {
+                #[cfg(feature = #feature)]
+                #dynamic
+            });
+        } else {
+            ret.block.stmts.push(parse_quote! { #dynamic });
         }
+    } else {
+        let default = impl_tracable_parser_default(&attr, item);
+        let trace = impl_tracable_parser_trace(&attr, item);
+        ret.block.stmts.push(default);
+        ret.block.stmts.push(trace);
     }
+
+    ret.into_token_stream().into()
 }
 
-fn impl_tracable_parser_trace(item: &ItemFn) -> Stmt {
-    let ident = &item.sig.ident;
+fn feature_name(attr: &TracableAttr) -> &str {
+    attr.feature.as_deref().unwrap_or("trace")
+}
 
-    let input = if let Some(x) = &item.sig.inputs.first() {
+fn parser_input(item: &ItemFn) -> &Pat {
+    if let Some(x) = &item.sig.inputs.first() {
         match x {
             FnArg::Typed(arg) => &arg.pat,
             _ => panic!("function with #[tracable_parser] must have an argument"),
         }
     } else {
         panic!("function with #[tracable_parser] must have an argument");
-    };
+    }
+}
 
-    let body = item.block.as_ref();
+fn parser_name(attr: &TracableAttr, item: &ItemFn) -> String {
+    match &attr.name {
+        Some(x) => x.clone(),
+        None => item.sig.ident.to_string(),
+    }
+}
+
+// The memo works without trace, so it is inserted into all expansions.
+// The memo hits are counted only if trace is enabled.
+fn impl_memo(attr: &TracableAttr, item: &ItemFn, body: &Block, trace: bool) -> Block {
+    if attr.memo {
+        let ident = &item.sig.ident;
+        let input = parser_input(item);
+        let name = parser_name(attr, item);
+        let key = quote! { concat!(module_path!(), "::", stringify!(#ident)) };
+        if trace {
+            parse_quote! {
+                {
+                    nom_tracable::memo_trace(#input, #key, #name, |#input| #body)
+                }
+            }
+        } else {
+            parse_quote! {
+                {
+                    nom_tracable::memo(#input, #key, |#input| #body)
+                }
+            }
+        }
+    } else {
+        body.clone()
+    }
+}
+
+// The depth check works without trace, so it is inserted into all expansions
+fn impl_depth_guard(attr: &TracableAttr, item: &ItemFn, body: &Block) -> Block {
+    if attr.guard {
+        let input = parser_input(item);
+        let name = parser_name(attr, item);
+        parse_quote! {
+            {
+                nom_tracable::depth_guard(#input, #name, |#input| #body)
+            }
+        }
+    } else {
+        body.clone()
+    }
+}
+
+fn impl_tracable_parser_default(attr: &TracableAttr, item: &ItemFn) -> Stmt {
+    let body = impl_memo(attr, item, &item.block, false);
+    let body = impl_depth_guard(attr, item, &body);
+    let feature = feature_name(attr);
+    parse_quote! {
+        #[cfg(not(feature = #feature))]
+        {
+            #body
+        }
+    }
+}
 
+fn impl_tracable_parser_dynamic(attr: &TracableAttr, item: &ItemFn) -> Block {
+    let body = impl_memo(attr, item, &item.block, false);
+    let body = impl_depth_guard(attr, item, &body);
+    let trace = impl_tracable_parser_trace_body(attr, item);
     parse_quote! {
-        #[cfg(feature = "trace")]
         {
-            let (depth, #input) = nom_tracable::forward_trace(#input, stringify!(#ident));
+            if nom_tracable::trace_enabled() {
+                #trace
+            } else {
+                #body
+            }
+        }
+    }
+}
+
+fn impl_tracable_parser_trace(attr: &TracableAttr, item: &ItemFn) -> Stmt {
+    let trace = impl_tracable_parser_trace_body(attr, item);
+    let feature = feature_name(attr);
+    parse_quote! {
+        #[cfg(feature = #feature)]
+        #trace
+    }
+}
+
+fn impl_tracable_parser_trace_body(attr: &TracableAttr, item: &ItemFn) -> Block {
+    let input = parser_input(item);
+    let body = item.block.as_ref();
+    let name = parser_name(attr, item);
+    let fold = attr.fold;
+    let silent = attr.silent;
 
-            let body_ret = {
+    let body_ret: Block = if attr.memo {
+        impl_memo(attr, item, &item.block, true)
+    } else {
+        parse_quote! {
+            {
                 let body = || { #body };
                 body()
+            }
+        }
+    };
+
+    // The guard requires `nom::error::ParseError`, so it is inserted only if `guard` is specified
+    let body_ret: Block = if attr.guard {
+        let body_ret = impl_depth_guard(attr, item, &body_ret);
+        parse_quote! {
+            {
+                match nom_tracable::guard_trace(#input, #name) {
+                    Ok(#input) => #body_ret,
+                    Err(x) => x,
+                }
+            }
+        }
+    } else {
+        body_ret
+    };
+
+    parse_quote! {
+        {
+            let options = nom_tracable::ParserOptions {
+                fold: #fold,
+                silent: #silent,
             };
+            let (depth, #input) = nom_tracable::forward_trace_with(#input, #name, options);
+
+            let body_ret = #body_ret;
 
-            nom_tracable::backward_trace(body_ret, stringify!(#ident), depth)
+            nom_tracable::backward_trace(body_ret, #name, depth)
         }
     }
 }
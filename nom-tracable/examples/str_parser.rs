@@ -2,7 +2,10 @@ use nom::branch::*;
 use nom::character::complete::*;
 use nom::IResult;
 use nom_locate::LocatedSpan;
-use nom_tracable::{cumulative_histogram, histogram, tracable_parser, TracableInfo};
+use nom_tracable::{
+    cumulative_histogram, histogram, reparse_histogram, timing_histogram, tracable_parser,
+    TracableInfo,
+};
 
 // Input type must implement trait Tracable
 // nom_locate::LocatedSpan<T, TracableInfo> implements it.
@@ -46,10 +49,15 @@ pub fn term_internal(s: Span) -> IResult<Span, String> {
 
 fn main() {
     // Configure trace setting
-    let info = TracableInfo::new().parser_width(64).fold("term");
+    let info = TracableInfo::new()
+        .parser_width(64)
+        .fold("term")
+        .reparse(true);
     let _ret = expr(LocatedSpan::new_extra("1-1+1+1-1", info));
 
     // Show histogram
     histogram();
     cumulative_histogram();
+    timing_histogram();
+    reparse_histogram();
 }
@@ -2,7 +2,9 @@ use nom::branch::*;
 use nom::character::complete::*;
 use nom::{IResult, InputIter, Needed, Slice};
 use nom_locate::LocatedSpan;
-use nom_tracable::{cumulative_histogram, histogram, tracable_parser, TracableInfo};
+use nom_tracable::{
+    cumulative_histogram, histogram, timing_histogram, tracable_parser, TracableInfo,
+};
 #[cfg(feature = "trace")]
 use nom_tracable::{HasTracableInfo, Tracable};
 
@@ -38,7 +40,7 @@ impl<'a> Tracable for Span<'a> {
         let fragment: String = String::from_utf8_lossy(self.0.fragment())
             .lines()
             .next()
-            .unwrap_or_else(|| "")
+            .unwrap_or("")
             .chars()
             .take(info.fragment_width)
             .collect();
@@ -48,6 +50,10 @@ impl<'a> Tracable for Span<'a> {
     fn header(&self) -> String {
         self.0.header()
     }
+
+    fn offset(&self) -> Option<usize> {
+        self.0.offset()
+    }
 }
 impl<'a> InputIter for Span<'a> {
     type Item = u8;
@@ -126,4 +132,5 @@ fn main() {
     // Show histogram
     histogram();
     cumulative_histogram();
+    timing_histogram();
 }
@@ -2,7 +2,9 @@ use nom::branch::*;
 use nom::character::complete::*;
 use nom::IResult;
 use nom_locate::LocatedSpan;
-use nom_tracable::{cumulative_histogram, histogram, tracable_parser, TracableInfo};
+use nom_tracable::{
+    cumulative_histogram, histogram, timing_histogram, tracable_parser, TracableInfo,
+};
 
 // Input type must implement trait Tracable
 // nom_locate::LocatedSpan<T, TracableInfo> implements it.
@@ -55,4 +57,5 @@ fn main() {
     // Show histogram
     histogram();
     cumulative_histogram();
+    timing_histogram();
 }
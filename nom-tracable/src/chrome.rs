@@ -0,0 +1,87 @@
+use crate::json::json_string;
+use crate::{TracableInfo, TraceEvent, TraceSink};
+use std::io::Write;
+
+/// Sink to write trace events by Chrome Trace Event Format.
+///
+/// The output can be loaded by `chrome://tracing` or [Perfetto UI](https://ui.perfetto.dev).
+/// Each parser call is shown as a slice, and `custom_trace` is shown as an instant event.
+/// The closing bracket of the JSON array is written when the sink is dropped,
+/// but the viewers can load the output without it.
+pub struct ChromeTraceSink {
+    writer: Box<dyn Write>,
+    started: bool,
+}
+
+impl ChromeTraceSink {
+    /// Create a sink writing to `writer`.
+    pub fn new<W: Write + 'static>(writer: W) -> Self {
+        ChromeTraceSink {
+            writer: Box::new(writer),
+            started: false,
+        }
+    }
+
+    fn write_event(&mut self, event: &str) {
+        let separator = if self.started { ",\n" } else { "[\n" };
+        self.started = true;
+        write!(self.writer, "{}{}", separator, event).unwrap();
+    }
+}
+
+impl TraceSink for ChromeTraceSink {
+    fn event(&mut self, _info: &TracableInfo, event: &TraceEvent) {
+        // The offset and the consumed length are omitted if the input has no offset
+        let number = |key: &str, x: &Option<usize>| match x {
+            Some(x) => format!(",\"{}\":{}", key, x),
+            None => String::new(),
+        };
+        let (phase, args) = match event {
+            TraceEvent::Enter { offset, .. } => ("B", number("offset", offset)),
+            TraceEvent::ExitOk { consumed, .. } => (
+                "E",
+                format!(",\"result\":\"ok\"{}", number("consumed", consumed)),
+            ),
+            TraceEvent::ExitErr { kind, .. } => ("E", format!(",\"result\":\"{}\"", kind.as_str())),
+            TraceEvent::Custom {
+                offset, message, ..
+            } => (
+                "i",
+                format!(
+                    "{},\"message\":{}",
+                    number("offset", offset),
+                    json_string(message)
+                ),
+            ),
+        };
+        let args = format!("{{{}}}", args.trim_start_matches(','));
+
+        let scope = if phase == "i" { ",\"s\":\"t\"" } else { "" };
+        let line = format!(
+            "{{\"name\":{},\"cat\":\"parser\",\"ph\":\"{}\"{},\"ts\":{:.3},\"pid\":1,\"tid\":1,\"args\":{}}}",
+            json_string(event.name()),
+            phase,
+            scope,
+            event.timestamp().as_secs_f64() * 1_000_000.0,
+            args
+        );
+        self.write_event(&line);
+
+        let finished = matches!(
+            event,
+            TraceEvent::ExitOk { depth: 0, .. } | TraceEvent::ExitErr { depth: 0, .. }
+        );
+        if finished {
+            self.writer.flush().unwrap();
+        }
+    }
+}
+
+impl Drop for ChromeTraceSink {
+    fn drop(&mut self) {
+        if self.started {
+            let _ = writeln!(self.writer, "\n]");
+            let _ = self.writer.flush();
+        }
+    }
+}
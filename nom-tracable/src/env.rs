@@ -0,0 +1,226 @@
+use crate::TracableInfo;
+#[cfg(feature = "trace")]
+use crate::{
+    set_trace_enabled, set_trace_sink, ChromeTraceSink, ColorMode, FailureMode, FailureSink,
+    FoldedStackSink, FoldedWeight, JsonLinesSink, RecursionGuard, TableSink, Theme, TraceSink,
+};
+#[cfg(feature = "trace")]
+use std::io::BufWriter;
+
+#[cfg(feature = "trace")]
+impl TracableInfo {
+    /// Create `TracableInfo` from environment variables.
+    ///
+    /// The following variables are available:
+    ///
+    /// * `NOM_TRACE`: whether trace is enabled (`1`/`0`).
+    ///   This calls `set_trace_enabled` too.
+    /// * `NOM_TRACE_FOLD`: comma separated names of folding parsers (e.g. `term,ws`)
+    /// * `NOM_TRACE_INCLUDE`: the pattern of displayed parsers (e.g. `expr*|term`)
+    /// * `NOM_TRACE_EXCLUDE`: the pattern of hidden parsers (e.g. `ws|comment`)
+    /// * `NOM_TRACE_COLOR`: whether color is enabled (`always`/`never`/`auto`)
+    /// * `NOM_TRACE_SNIPPET`: whether the source snippet is displayed (`1`/`0`)
+    /// * `NOM_TRACE_RECURSION`: the behavior of left recursion (`off`/`error`/`panic`)
+    /// * `NOM_TRACE_THEME`: the color theme of `TableSink` (`dark`/`light`/`high-contrast`)
+    /// * `NOM_TRACE_MAX_TRACE_DEPTH`: the max depth of displayed trace
+    /// * `NOM_TRACE_MIN_TRACE_DEPTH`: the min depth of displayed trace
+    /// * `NOM_TRACE_OFFSET`: the range of input offset in which trace is displayed (e.g. `48000..49000`)
+    /// * `NOM_TRACE_MAX_LINES`: the max number of displayed trace lines
+    /// * `NOM_TRACE_MODE`: the parsers to be displayed (`full`/`failures`/`furthest`).
+    ///   `failures` and `furthest` wrap the sink by `FailureSink`.
+    /// * `NOM_TRACE_OUTPUT`: the output of trace (`stdout`/`stderr`/file path).
+    ///   The sink of the current thread is replaced by the sink selected from the extension of the file:
+    ///   `JsonLinesSink` for `.jsonl`, `ChromeTraceSink` for `.json`,
+    ///   `FoldedStackSink` for `.folded` and `TableSink` for the others.
+    ///
+    /// Invalid values are ignored with a warning to stderr.
+    pub fn from_env() -> Self {
+        let mut info = TracableInfo::new();
+
+        if let Some(x) = env_bool("NOM_TRACE") {
+            set_trace_enabled(x);
+            info = info.forward(x).backward(x).custom(x);
+        }
+
+        if let Some(x) = env_var("NOM_TRACE_FOLD") {
+            for name in x.split(',').map(|x| x.trim()).filter(|x| !x.is_empty()) {
+                info = info.fold(name);
+            }
+        }
+
+        if let Some(x) = env_var("NOM_TRACE_INCLUDE") {
+            info = info.include(&x);
+        }
+
+        if let Some(x) = env_var("NOM_TRACE_EXCLUDE") {
+            info = info.exclude(&x);
+        }
+
+        if let Some(x) = env_var("NOM_TRACE_COLOR") {
+            match x.to_ascii_lowercase().as_str() {
+                "always" | "1" | "true" => info = info.color(ColorMode::Always),
+                "never" | "0" | "false" => info = info.color(ColorMode::Never),
+                "auto" => info = info.color(ColorMode::Auto),
+                _ => warn("NOM_TRACE_COLOR", &x),
+            }
+        }
+
+        if let Some(x) = env_bool("NOM_TRACE_SNIPPET") {
+            info = info.snippet(x);
+        }
+
+        if let Some(x) = env_var("NOM_TRACE_RECURSION") {
+            match x.to_ascii_lowercase().as_str() {
+                "off" => info = info.recursion_guard(RecursionGuard::Off),
+                "error" => info = info.recursion_guard(RecursionGuard::Error),
+                "panic" => info = info.recursion_guard(RecursionGuard::Panic),
+                _ => warn("NOM_TRACE_RECURSION", &x),
+            }
+        }
+
+        if let Some(x) = env_var("NOM_TRACE_MAX_TRACE_DEPTH") {
+            match x.parse() {
+                Ok(x) => info = info.max_trace_depth(x),
+                Err(_) => warn("NOM_TRACE_MAX_TRACE_DEPTH", &x),
+            }
+        }
+
+        if let Some(x) = env_var("NOM_TRACE_MIN_TRACE_DEPTH") {
+            match x.parse() {
+                Ok(x) => info = info.min_trace_depth(x),
+                Err(_) => warn("NOM_TRACE_MIN_TRACE_DEPTH", &x),
+            }
+        }
+
+        if let Some(x) = env_var("NOM_TRACE_OFFSET") {
+            match parse_range(&x) {
+                Some(x) => info = info.offset_window(x),
+                None => warn("NOM_TRACE_OFFSET", &x),
+            }
+        }
+
+        if let Some(x) = env_var("NOM_TRACE_MAX_LINES") {
+            match x.parse() {
+                Ok(x) => info = info.max_trace_lines(x),
+                Err(_) => warn("NOM_TRACE_MAX_LINES", &x),
+            }
+        }
+
+        let theme =
+            env_var("NOM_TRACE_THEME").and_then(|x| match x.to_ascii_lowercase().as_str() {
+                "dark" => Some(Theme::dark()),
+                "light" => Some(Theme::light()),
+                "high-contrast" => Some(Theme::high_contrast()),
+                _ => {
+                    warn("NOM_TRACE_THEME", &x);
+                    None
+                }
+            });
+
+        let mode = env_var("NOM_TRACE_MODE").and_then(|x| match x.to_ascii_lowercase().as_str() {
+            "full" => None,
+            "failures" => Some(FailureMode::All),
+            "furthest" => Some(FailureMode::Furthest),
+            _ => {
+                warn("NOM_TRACE_MODE", &x);
+                None
+            }
+        });
+
+        let sink: Option<Box<dyn TraceSink>> = match env_var("NOM_TRACE_OUTPUT") {
+            Some(x) => output_sink(&x, theme.unwrap_or_default()),
+            None if theme.is_some() || mode.is_some() => Some(Box::new(
+                TableSink::default().theme(theme.unwrap_or_default()),
+            )),
+            None => None,
+        };
+
+        match (sink, mode) {
+            (Some(sink), Some(mode)) => {
+                set_trace_sink(FailureSink::new(sink, mode));
+            }
+            (Some(sink), None) => {
+                set_trace_sink(sink);
+            }
+            _ => (),
+        }
+
+        info
+    }
+}
+
+#[cfg(not(feature = "trace"))]
+impl TracableInfo {
+    pub fn from_env() -> Self {
+        TracableInfo::new()
+    }
+}
+
+#[cfg(feature = "trace")]
+fn env_var(key: &str) -> Option<String> {
+    std::env::var(key).ok().filter(|x| !x.is_empty())
+}
+
+#[cfg(feature = "trace")]
+fn env_bool(key: &str) -> Option<bool> {
+    let x = env_var(key)?;
+    match x.to_ascii_lowercase().as_str() {
+        "1" | "true" | "on" | "yes" => Some(true),
+        "0" | "false" | "off" | "no" => Some(false),
+        _ => {
+            warn(key, &x);
+            None
+        }
+    }
+}
+
+#[cfg(feature = "trace")]
+fn parse_range(x: &str) -> Option<std::ops::Range<usize>> {
+    let (start, end) = x.split_once("..")?;
+    let start = if start.is_empty() {
+        0
+    } else {
+        start.trim().parse().ok()?
+    };
+    let end = if end.is_empty() {
+        usize::MAX
+    } else {
+        end.trim().parse().ok()?
+    };
+    Some(start..end)
+}
+
+#[cfg(feature = "trace")]
+fn warn(key: &str, value: &str) {
+    eprintln!("nom-tracable: ignored invalid value of {}: {}", key, value);
+}
+
+#[cfg(feature = "trace")]
+fn output_sink(path: &str, theme: Theme) -> Option<Box<dyn TraceSink>> {
+    match path {
+        "stdout" | "-" => return Some(Box::new(TableSink::stdout().theme(theme))),
+        "stderr" => return Some(Box::new(TableSink::stderr().theme(theme))),
+        _ => (),
+    }
+
+    let file = match std::fs::File::create(path) {
+        Ok(x) => BufWriter::new(x),
+        Err(x) => {
+            eprintln!("nom-tracable: failed to create {}: {}", path, x);
+            return None;
+        }
+    };
+
+    let extension = std::path::Path::new(path)
+        .extension()
+        .and_then(|x| x.to_str())
+        .unwrap_or("");
+
+    let sink: Box<dyn TraceSink> = match extension {
+        "jsonl" | "ndjson" => Box::new(JsonLinesSink::new(file)),
+        "json" => Box::new(ChromeTraceSink::new(file)),
+        "folded" => Box::new(FoldedStackSink::new(file, FoldedWeight::Calls)),
+        _ => Box::new(TableSink::new(file).theme(theme)),
+    };
+    Some(sink)
+}
@@ -0,0 +1,147 @@
+use std::time::Duration;
+
+/// Kind of parser failure.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TraceErrorKind {
+    /// `nom::Err::Incomplete`
+    Incomplete,
+    /// `nom::Err::Error`
+    Error,
+    /// `nom::Err::Failure`
+    Failure,
+}
+
+impl TraceErrorKind {
+    /// Lowercase name of the kind.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TraceErrorKind::Incomplete => "incomplete",
+            TraceErrorKind::Error => "error",
+            TraceErrorKind::Failure => "failure",
+        }
+    }
+
+    #[cfg(feature = "trace")]
+    pub(crate) fn from_err<E>(err: &nom::Err<E>) -> Self {
+        match err {
+            nom::Err::Incomplete(_) => TraceErrorKind::Incomplete,
+            nom::Err::Error(_) => TraceErrorKind::Error,
+            nom::Err::Failure(_) => TraceErrorKind::Failure,
+        }
+    }
+}
+
+/// Event of trace.
+///
+/// `count` is the forward count for `Enter` and the backward count for `ExitOk`/`ExitErr`.
+/// `offset` and `location` (line and column) are the input position at which the parser was entered,
+/// and `None` if the input type doesn't provide them.
+/// `input` is the input formatted by `Tracable::format`,
+/// and `fragment` is the input fragment given by `Tracable::display_fragment`.
+/// `snippet` is the source line with a caret given by `Tracable::snippet` if `TracableInfo::snippet` is enabled.
+/// `timestamp` is the elapsed time from the first trace event of the thread.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TraceEvent {
+    /// A parser is entered.
+    Enter {
+        name: String,
+        depth: usize,
+        count: usize,
+        offset: Option<usize>,
+        location: Option<(u32, usize)>,
+        folded: bool,
+        fragment: String,
+        input: String,
+        timestamp: Duration,
+    },
+    /// A parser succeeded.
+    ///
+    /// `fragment` and `input` are the rest of input after the parser.
+    ExitOk {
+        name: String,
+        depth: usize,
+        count: usize,
+        offset: Option<usize>,
+        location: Option<(u32, usize)>,
+        consumed: Option<usize>,
+        folded: bool,
+        fragment: String,
+        input: String,
+        timestamp: Duration,
+    },
+    /// A parser failed.
+    ExitErr {
+        name: String,
+        depth: usize,
+        count: usize,
+        offset: Option<usize>,
+        location: Option<(u32, usize)>,
+        folded: bool,
+        kind: TraceErrorKind,
+        snippet: Option<String>,
+        timestamp: Duration,
+    },
+    /// A custom trace point by `custom_trace`.
+    Custom {
+        name: String,
+        depth: usize,
+        offset: Option<usize>,
+        location: Option<(u32, usize)>,
+        message: String,
+        color: String,
+        snippet: Option<String>,
+        timestamp: Duration,
+    },
+}
+
+impl TraceEvent {
+    /// Parser name of the event.
+    pub fn name(&self) -> &str {
+        match self {
+            TraceEvent::Enter { name, .. } => name,
+            TraceEvent::ExitOk { name, .. } => name,
+            TraceEvent::ExitErr { name, .. } => name,
+            TraceEvent::Custom { name, .. } => name,
+        }
+    }
+
+    /// Depth of the event.
+    pub fn depth(&self) -> usize {
+        match self {
+            TraceEvent::Enter { depth, .. } => *depth,
+            TraceEvent::ExitOk { depth, .. } => *depth,
+            TraceEvent::ExitErr { depth, .. } => *depth,
+            TraceEvent::Custom { depth, .. } => *depth,
+        }
+    }
+
+    /// Input offset of the event.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            TraceEvent::Enter { offset, .. } => *offset,
+            TraceEvent::ExitOk { offset, .. } => *offset,
+            TraceEvent::ExitErr { offset, .. } => *offset,
+            TraceEvent::Custom { offset, .. } => *offset,
+        }
+    }
+
+    /// Timestamp of the event.
+    pub fn timestamp(&self) -> Duration {
+        match self {
+            TraceEvent::Enter { timestamp, .. } => *timestamp,
+            TraceEvent::ExitOk { timestamp, .. } => *timestamp,
+            TraceEvent::ExitErr { timestamp, .. } => *timestamp,
+            TraceEvent::Custom { timestamp, .. } => *timestamp,
+        }
+    }
+
+    /// Line and column of the event.
+    pub fn location(&self) -> Option<(u32, usize)> {
+        match self {
+            TraceEvent::Enter { location, .. } => *location,
+            TraceEvent::ExitOk { location, .. } => *location,
+            TraceEvent::ExitErr { location, .. } => *location,
+            TraceEvent::Custom { location, .. } => *location,
+        }
+    }
+}
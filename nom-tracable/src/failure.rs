@@ -0,0 +1,179 @@
+use crate::{TracableInfo, TraceEvent, TraceSink};
+use std::time::Duration;
+
+/// Mode of `FailureSink`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailureMode {
+    /// Failed parsers and their ancestors
+    All,
+    /// Ancestors of the failed parser at the furthest offset
+    Furthest,
+}
+
+/// Sink to display only the failure branches of trace.
+///
+/// The events are buffered while the top-level parser is running,
+/// and only the branches selected by `FailureMode` are passed to the inner sink when it returns.
+/// Custom trace is passed if its parser is selected.
+/// Both forward and backward trace must be enabled to reconstruct the call tree.
+///
+/// ```
+/// # use nom::character::complete::*;
+/// # use nom::IResult;
+/// # use nom_locate::LocatedSpan;
+/// # use nom_tracable::{set_trace_sink, tracable_parser, FailureMode, FailureSink, TableSink, TracableInfo};
+/// #
+/// # type Span<'a> = LocatedSpan<&'a str, TracableInfo>;
+/// #
+/// # #[tracable_parser]
+/// # pub fn term(s: Span) -> IResult<Span, String> {
+/// #     let (s, x) = char('1')(s)?;
+/// #     Ok((s, x.to_string()))
+/// # }
+/// #
+/// set_trace_sink(FailureSink::new(TableSink::stdout(), FailureMode::Furthest));
+/// let ret = term(LocatedSpan::new_extra("2", TracableInfo::new()));
+/// ```
+pub struct FailureSink {
+    inner: Box<dyn TraceSink>,
+    mode: FailureMode,
+    events: Vec<(TracableInfo, TraceEvent)>,
+    depth: usize,
+}
+
+impl FailureSink {
+    /// Create a sink passing the selected events to `inner`.
+    pub fn new<S: TraceSink + 'static>(inner: S, mode: FailureMode) -> Self {
+        FailureSink {
+            inner: Box::new(inner),
+            mode,
+            events: Vec::new(),
+            depth: 0,
+        }
+    }
+
+    fn flush(&mut self) {
+        let events = std::mem::take(&mut self.events);
+        let selected = select(&events, self.mode);
+        for (i, (info, event)) in events.iter().enumerate() {
+            if selected[i] {
+                self.inner.event(info, event);
+            }
+        }
+    }
+}
+
+struct Node {
+    enter: usize,
+    exit: Option<usize>,
+    parent: Option<usize>,
+    failed: bool,
+}
+
+/// Select the events to be displayed.
+fn select(events: &[(TracableInfo, TraceEvent)], mode: FailureMode) -> Vec<bool> {
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut stack: Vec<usize> = Vec::new();
+    let mut custom_owner = vec![None; events.len()];
+
+    for (i, (_, event)) in events.iter().enumerate() {
+        match event {
+            TraceEvent::Enter { .. } => {
+                stack.push(nodes.len());
+                nodes.push(Node {
+                    enter: i,
+                    exit: None,
+                    parent: stack.iter().rev().nth(1).copied(),
+                    failed: false,
+                });
+            }
+            TraceEvent::ExitOk { .. } | TraceEvent::ExitErr { .. } => {
+                if let Some(x) = stack.pop() {
+                    nodes[x].exit = Some(i);
+                    nodes[x].failed = matches!(event, TraceEvent::ExitErr { .. });
+                }
+            }
+            TraceEvent::Custom { .. } => custom_owner[i] = stack.last().copied(),
+        }
+    }
+
+    let mut keep = vec![false; nodes.len()];
+    let mark = |keep: &mut Vec<bool>, mut x: Option<usize>| {
+        while let Some(node) = x {
+            if keep[node] {
+                break;
+            }
+            keep[node] = true;
+            x = nodes[node].parent;
+        }
+    };
+
+    match mode {
+        FailureMode::All => {
+            for (i, node) in nodes.iter().enumerate() {
+                if node.failed {
+                    mark(&mut keep, Some(i));
+                }
+            }
+        }
+        FailureMode::Furthest => {
+            let furthest = nodes
+                .iter()
+                .enumerate()
+                .filter(|(_, x)| x.failed)
+                .map(|(i, x)| {
+                    let event = &events[x.enter].1;
+                    (event.offset(), event.depth(), std::cmp::Reverse(i))
+                })
+                .max();
+            if let Some((_, _, std::cmp::Reverse(i))) = furthest {
+                mark(&mut keep, Some(i));
+            }
+        }
+    }
+
+    let mut selected = vec![false; events.len()];
+    for (node, keep) in nodes.iter().zip(keep.iter()) {
+        if *keep {
+            selected[node.enter] = true;
+            if let Some(x) = node.exit {
+                selected[x] = true;
+            }
+        }
+    }
+    for (i, owner) in custom_owner.iter().enumerate() {
+        if let Some(x) = owner {
+            selected[i] = keep[*x];
+        }
+    }
+    selected
+}
+
+impl TraceSink for FailureSink {
+    fn header(&mut self, info: &TracableInfo, header: &str) {
+        self.inner.header(info, header);
+    }
+
+    fn event(&mut self, info: &TracableInfo, event: &TraceEvent) {
+        self.events.push((*info, event.clone()));
+
+        match event {
+            TraceEvent::Enter { .. } => self.depth += 1,
+            TraceEvent::ExitOk { .. } | TraceEvent::ExitErr { .. } => {
+                self.depth = self.depth.saturating_sub(1);
+                if self.depth == 0 {
+                    self.flush();
+                }
+            }
+            TraceEvent::Custom { .. } => (),
+        }
+    }
+
+    fn histogram(&mut self, title: &str, histogram: &[(&str, usize)]) {
+        self.inner.histogram(title, histogram);
+    }
+
+    fn timing_histogram(&mut self, histogram: &[(&str, Duration, Duration)]) {
+        self.inner.timing_histogram(histogram);
+    }
+}
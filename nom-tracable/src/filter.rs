@@ -0,0 +1,89 @@
+use std::ops::Range;
+
+/// Filter of displayed trace set by `TracableInfo`.
+///
+/// This is interned at the table shared by all threads and referred by `TracableInfo::filter`
+/// to keep `TracableInfo` small and `Copy`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct TraceFilter {
+    pub(crate) min_depth: usize,
+    pub(crate) offset: Range<usize>,
+    pub(crate) max_lines: usize,
+    pub(crate) include: Vec<String>,
+    pub(crate) exclude: Vec<String>,
+}
+
+#[allow(dead_code)]
+impl TraceFilter {
+    pub(crate) const DEFAULT: TraceFilter = TraceFilter {
+        min_depth: 0,
+        offset: 0..usize::MAX,
+        max_lines: usize::MAX,
+        include: Vec::new(),
+        exclude: Vec::new(),
+    };
+
+    /// Whether the parser at `depth` and `offset` is displayed after `lines` lines.
+    ///
+    /// The input without offset is not filtered by the offset window.
+    pub(crate) fn visible(&self, depth: usize, offset: Option<usize>, lines: usize) -> bool {
+        let in_window = match offset {
+            Some(x) => self.offset.contains(&x),
+            None => true,
+        };
+        self.min_depth <= depth && in_window && lines < self.max_lines
+    }
+
+    /// Whether the parser named `name` is displayed by include/exclude patterns.
+    pub(crate) fn included(&self, name: &str) -> bool {
+        let include =
+            self.include.is_empty() || self.include.iter().any(|x| pattern_match(x, name));
+        let exclude = self.exclude.iter().any(|x| pattern_match(x, name));
+        include && !exclude
+    }
+}
+
+impl Default for TraceFilter {
+    fn default() -> Self {
+        TraceFilter::DEFAULT
+    }
+}
+
+/// Match `name` with `pattern`.
+///
+/// `pattern` is `|` separated glob patterns, and `*` matches any string and `?` matches any character.
+pub(crate) fn pattern_match(pattern: &str, name: &str) -> bool {
+    let name: Vec<char> = name.chars().collect();
+    pattern.split('|').any(|x| {
+        let glob: Vec<char> = x.trim().chars().collect();
+        glob_match(&glob, &name)
+    })
+}
+
+fn glob_match(glob: &[char], name: &[char]) -> bool {
+    let (mut g, mut n) = (0, 0);
+    let mut backtrack = None;
+
+    while n < name.len() {
+        match glob.get(g) {
+            Some('*') => {
+                backtrack = Some((g, n));
+                g += 1;
+            }
+            Some(x) if *x == '?' || *x == name[n] => {
+                g += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((bg, bn)) => {
+                    backtrack = Some((bg, bn + 1));
+                    g = bg + 1;
+                    n = bn + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    glob[g..].iter().all(|x| *x == '*')
+}
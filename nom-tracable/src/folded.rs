@@ -0,0 +1,108 @@
+use crate::{TracableInfo, TraceEvent, TraceSink};
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Weight of folded stack.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FoldedWeight {
+    /// Call count of parser
+    Calls,
+    /// Bytes consumed by parser
+    Bytes,
+    /// Elapsed time of parser in microseconds
+    Time,
+}
+
+/// Sink to write parser call stacks by folded stack format.
+///
+/// Each line has the semicolon separated call stack and the weight of the top parser like below:
+///
+/// ```text
+/// expr;expr_plus;term;term_internal 1
+/// ```
+///
+/// The output can be converted to flamegraph by `inferno-flamegraph` or `flamegraph.pl`.
+/// The lines are written when the top-level parser is finished.
+/// Both forward and backward trace must be enabled to reconstruct call stacks.
+pub struct FoldedStackSink {
+    writer: Box<dyn Write>,
+    weight: FoldedWeight,
+    stack: Vec<FoldedFrame>,
+    stacks: HashMap<String, u64>,
+}
+
+struct FoldedFrame {
+    name: String,
+    start: u64,
+    children: u64,
+}
+
+impl FoldedStackSink {
+    /// Create a sink writing to `writer`.
+    pub fn new<W: Write + 'static>(writer: W, weight: FoldedWeight) -> Self {
+        FoldedStackSink {
+            writer: Box::new(writer),
+            weight,
+            stack: Vec::new(),
+            stacks: HashMap::new(),
+        }
+    }
+
+    fn write_stacks(&mut self) {
+        let mut stacks: Vec<_> = self.stacks.drain().collect();
+        stacks.sort();
+        for (stack, weight) in stacks {
+            writeln!(self.writer, "{} {}", stack, weight).unwrap();
+        }
+        self.writer.flush().unwrap();
+    }
+}
+
+impl TraceSink for FoldedStackSink {
+    fn event(&mut self, _info: &TracableInfo, event: &TraceEvent) {
+        match event {
+            TraceEvent::Enter {
+                name, timestamp, ..
+            } => {
+                self.stack.push(FoldedFrame {
+                    name: name.clone(),
+                    start: timestamp.as_micros() as u64,
+                    children: 0,
+                });
+            }
+            TraceEvent::ExitOk { .. } | TraceEvent::ExitErr { .. } => {
+                let total = match (self.weight, event) {
+                    (FoldedWeight::Calls, _) => 1,
+                    (FoldedWeight::Bytes, TraceEvent::ExitOk { consumed, .. }) => {
+                        consumed.unwrap_or(0) as u64
+                    }
+                    (FoldedWeight::Bytes, _) => 0,
+                    (FoldedWeight::Time, x) => {
+                        let start = self.stack.last().map(|x| x.start).unwrap_or(0);
+                        (x.timestamp().as_micros() as u64).saturating_sub(start)
+                    }
+                };
+
+                let path: Vec<_> = self.stack.iter().map(|x| x.name.as_str()).collect();
+                let path = path.join(";");
+
+                if let Some(frame) = self.stack.pop() {
+                    let weight = match self.weight {
+                        FoldedWeight::Calls => total,
+                        _ => total.saturating_sub(frame.children),
+                    };
+                    *self.stacks.entry(path).or_insert(0) += weight;
+
+                    if let Some(parent) = self.stack.last_mut() {
+                        parent.children += total;
+                    }
+                }
+
+                if self.stack.is_empty() {
+                    self.write_stacks();
+                }
+            }
+            TraceEvent::Custom { .. } => (),
+        }
+    }
+}
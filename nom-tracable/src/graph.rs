@@ -0,0 +1,160 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+/// Call graph of parsers.
+///
+/// This is generated by `call_graph`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CallGraph {
+    /// Parsers called at the top level
+    pub roots: BTreeSet<String>,
+    /// Edges from caller to callee sorted by names
+    pub edges: Vec<CallEdge>,
+}
+
+/// Edge of call graph.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CallEdge {
+    /// Caller parser name
+    pub caller: String,
+    /// Callee parser name
+    pub callee: String,
+    /// Call count
+    pub calls: usize,
+    /// Success count
+    pub success: usize,
+}
+
+impl CallEdge {
+    /// Ratio of success count to call count.
+    pub fn success_ratio(&self) -> f64 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            self.success as f64 / self.calls as f64
+        }
+    }
+
+    fn label(&self) -> String {
+        format!(
+            "{} calls, {:.0}% ok",
+            self.calls,
+            self.success_ratio() * 100.0
+        )
+    }
+}
+
+impl CallGraph {
+    /// All parsers in the graph.
+    pub fn parsers(&self) -> BTreeSet<&str> {
+        let mut ret: BTreeSet<&str> = self.roots.iter().map(|x| x.as_str()).collect();
+        for edge in &self.edges {
+            ret.insert(&edge.caller);
+            ret.insert(&edge.callee);
+        }
+        ret
+    }
+
+    /// Edge from `caller` to `callee`.
+    pub fn edge(&self, caller: &str, callee: &str) -> Option<&CallEdge> {
+        self.edges
+            .iter()
+            .find(|x| x.caller == caller && x.callee == callee)
+    }
+
+    /// Callers of `callee`.
+    pub fn callers<'a>(&'a self, callee: &'a str) -> impl Iterator<Item = &'a CallEdge> + 'a {
+        self.edges.iter().filter(move |x| x.callee == callee)
+    }
+
+    /// Graph in Graphviz DOT format.
+    ///
+    /// The roots are drawn with double circles,
+    /// and the edges are labeled with call count and success ratio.
+    pub fn to_dot(&self) -> String {
+        let mut ret = String::from("digraph parsers {\n");
+        for parser in self.parsers() {
+            let shape = if self.roots.contains(parser) {
+                "doublecircle"
+            } else {
+                "ellipse"
+            };
+            let _ = writeln!(ret, "    {} [shape={}];", dot_quote(parser), shape);
+        }
+        for edge in &self.edges {
+            let _ = writeln!(
+                ret,
+                "    {} -> {} [label={}];",
+                dot_quote(&edge.caller),
+                dot_quote(&edge.callee),
+                dot_quote(&edge.label())
+            );
+        }
+        ret.push_str("}\n");
+        ret
+    }
+
+    /// Graph in Mermaid flowchart format.
+    ///
+    /// The nodes are identified by `n0`, `n1`, ... in the order of `parsers`,
+    /// because parser names may be Mermaid keywords like `end`.
+    /// The roots are drawn with rounded boxes.
+    pub fn to_mermaid(&self) -> String {
+        let parsers: Vec<&str> = self.parsers().into_iter().collect();
+        let id = |name: &str| parsers.iter().position(|x| *x == name).unwrap_or(0);
+
+        let mut ret = String::from("flowchart TD\n");
+        for (i, parser) in parsers.iter().enumerate() {
+            let label = mermaid_quote(parser);
+            if self.roots.contains(*parser) {
+                let _ = writeln!(ret, "    n{}({})", i, label);
+            } else {
+                let _ = writeln!(ret, "    n{}[{}]", i, label);
+            }
+        }
+        for edge in &self.edges {
+            let _ = writeln!(
+                ret,
+                "    n{} -->|{}| n{}",
+                id(&edge.caller),
+                mermaid_quote(&edge.label()),
+                id(&edge.callee)
+            );
+        }
+        ret
+    }
+}
+
+/// Quote a string as DOT ID.
+fn dot_quote(x: &str) -> String {
+    let mut ret = String::from("\"");
+    for c in x.chars() {
+        match c {
+            '"' => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            '\n' => ret.push_str("\\n"),
+            '\r' => {}
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+/// Quote a string as Mermaid label.
+fn mermaid_quote(x: &str) -> String {
+    let mut ret = String::from("\"");
+    for c in x.chars() {
+        match c {
+            '"' => ret.push_str("#quot;"),
+            '#' => ret.push_str("#35;"),
+            '\n' => ret.push_str("<br>"),
+            '\r' => {}
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
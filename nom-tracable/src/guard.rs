@@ -0,0 +1,287 @@
+#[cfg(feature = "trace")]
+use crate::{custom_trace, Tracable};
+use crate::{HasTracableInfo, TracableInfo};
+use nom::error::{ErrorKind, ParseError};
+use nom::IResult;
+#[cfg(any(feature = "trace", feature = "guard"))]
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::fmt;
+
+/// Error kind returned by a parser re-entered at the same offset.
+///
+/// This is an existing kind of nom which may be returned by nom's combinators too.
+/// `guard_error` can be used to check whether the error is caused by the guard.
+pub const RECURSION_ERROR: ErrorKind = ErrorKind::Fix;
+
+/// Error kind returned by a parser exceeding `TracableInfo::max_depth`.
+///
+/// This is an existing kind of nom which may be returned by nom's combinators too.
+/// `guard_error` can be used to check whether the error is caused by the guard.
+pub const DEPTH_LIMIT_ERROR: ErrorKind = ErrorKind::TooLarge;
+
+/// Behavior when a parser re-enters itself at the same offset without consuming input.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RecursionGuard {
+    /// Nothing is checked
+    #[default]
+    Off,
+    /// The re-entered parser returns `nom::Err::Error` with `RECURSION_ERROR`.
+    ///
+    /// Because it is recoverable, `alt` tries the next alternative.
+    Error,
+    /// Panic with the cycle of parsers like `expr -> expr_plus -> expr`
+    Panic,
+}
+
+/// Behavior when the depth of parsers reaches `TracableInfo::max_depth`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DepthLimit {
+    /// The parser returns `nom::Err::Failure` with `DEPTH_LIMIT_ERROR`.
+    ///
+    /// Because it is not recoverable, the whole parse fails.
+    #[default]
+    Failure,
+    /// Print the error with the current call stack to stderr,
+    /// and return `nom::Err::Failure` with `DEPTH_LIMIT_ERROR` as `Failure`.
+    ///
+    /// The call stack is available only while trace is enabled.
+    Print,
+}
+
+/// Error detected by the guards of `#[tracable_parser(guard)]`.
+///
+/// This is got by `guard_error`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GuardError {
+    /// A parser re-entered at the same offset
+    Recursion {
+        /// Cycle of parsers like `expr -> expr_plus -> expr`
+        cycle: String,
+        /// Input offset
+        offset: usize,
+    },
+    /// The depth of parsers reached `TracableInfo::max_depth`
+    DepthLimit {
+        /// Parser name
+        parser: String,
+        /// The max depth
+        max_depth: usize,
+    },
+}
+
+impl fmt::Display for GuardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GuardError::Recursion { cycle, offset } => {
+                write!(f, "left recursion: {} at offset {}", cycle, offset)
+            }
+            GuardError::DepthLimit { parser, max_depth } => {
+                write!(f, "max depth {} exceeded by {}", max_depth, parser)
+            }
+        }
+    }
+}
+
+thread_local!(
+    static GUARD_ERROR: RefCell<Option<GuardError>> = const { RefCell::new(None) }
+);
+
+// The depth is kept out of `TracableInfo` so that `TracableInfo` is zero-sized without trace.
+#[cfg(any(feature = "trace", feature = "guard"))]
+thread_local!(
+    static GUARD_DEPTH: Cell<usize> = const { Cell::new(0) }
+);
+
+/// Get the error detected by the guards in the last parse.
+///
+/// `RECURSION_ERROR` and `DEPTH_LIMIT_ERROR` are existing kinds of nom,
+/// so this can be used to distinguish the errors caused by the guards.
+/// The error is kept even if the parse is recovered from it (e.g. by `alt`),
+/// and is reset when the outermost parser with `guard` argument is called.
+///
+/// ```
+/// # use nom::character::complete::*;
+/// # use nom::IResult;
+/// # use nom_locate::LocatedSpan;
+/// # use nom_tracable::{guard_error, tracable_parser, GuardError, TracableInfo};
+/// #
+/// # type Span<'a> = LocatedSpan<&'a str, TracableInfo>;
+/// #
+/// # #[tracable_parser(guard)]
+/// # pub fn paren(s: Span) -> IResult<Span, String> {
+/// #     let (s, _) = char('(')(s)?;
+/// #     let (s, x) = paren(s)?;
+/// #     let (s, _) = char(')')(s)?;
+/// #     Ok((s, x))
+/// # }
+/// #
+/// # fn main() {
+///     let ret = paren(LocatedSpan::new_extra("((((", TracableInfo::new().max_depth(2)));
+///     if cfg!(any(feature = "trace", feature = "guard")) {
+///         assert!(matches!(ret, Err(nom::Err::Failure(_))));
+///         assert_eq!(
+///             guard_error(),
+///             Some(GuardError::DepthLimit {
+///                 parser: String::from("paren"),
+///                 max_depth: 2
+///             })
+///         );
+///     }
+/// # }
+/// ```
+pub fn guard_error() -> Option<GuardError> {
+    GUARD_ERROR.with(|x| x.borrow().clone())
+}
+
+#[cfg(any(feature = "trace", feature = "guard"))]
+fn set_guard_error(error: Option<GuardError>) {
+    GUARD_ERROR.with(|x| *x.borrow_mut() = error);
+}
+
+impl TracableInfo {
+    /// Set the max depth of parsers with `#[tracable_parser(guard)]`.
+    ///
+    /// A guarded parser nested in `x` guarded parsers doesn't run its body,
+    /// and behaves as set by `depth_limit` instead of overflowing the stack by deeply nested input.
+    /// Unlike the other settings, this works without trace if `guard` feature is enabled.
+    #[cfg(any(feature = "trace", feature = "guard"))]
+    pub fn max_depth(mut self, x: usize) -> Self {
+        self.max_depth = x;
+        self
+    }
+
+    /// Set the behavior when the depth of parsers reaches `max_depth`.
+    #[cfg(any(feature = "trace", feature = "guard"))]
+    pub fn depth_limit(mut self, x: DepthLimit) -> Self {
+        self.depth_limit = x;
+        self
+    }
+
+    #[cfg(not(any(feature = "trace", feature = "guard")))]
+    pub fn max_depth(self, _x: usize) -> Self {
+        self
+    }
+
+    #[cfg(not(any(feature = "trace", feature = "guard")))]
+    pub fn depth_limit(self, _x: DepthLimit) -> Self {
+        self
+    }
+}
+
+/// Function to check the guards of parser which require trace before running the parser body.
+/// This is inserted by `#[tracable_parser(guard)]`.
+///
+/// This returns the input if the parser can be run, or the error result otherwise.
+#[cfg(feature = "trace")]
+pub fn guard_trace<T, U, V>(input: T, name: &str) -> Result<T, IResult<T, U, V>>
+where
+    T: Tracable,
+    V: ParseError<T>,
+{
+    let info = input.get_tracable_info();
+
+    // The input without offset can't be checked because the re-entry can't be distinguished
+    if let (RecursionGuard::Error | RecursionGuard::Panic, Some(offset)) =
+        (info.recursion_guard, input.offset())
+    {
+        let cycle =
+            crate::TRACABLE_STORAGE.with(|storage| storage.borrow().recursion_cycle(name, offset));
+
+        if let Some(cycle) = cycle {
+            let error = GuardError::Recursion { cycle, offset };
+            if info.recursion_guard == RecursionGuard::Panic {
+                panic!("{}", error);
+            }
+            custom_trace(&input, name, &error.to_string(), "");
+            set_guard_error(Some(error));
+            return Err(Err(nom::Err::Error(V::from_error_kind(
+                input,
+                RECURSION_ERROR,
+            ))));
+        }
+    }
+
+    if GUARD_DEPTH.with(|x| x.get()) >= info.max_depth {
+        let message = format!("max depth {} exceeded", info.max_depth);
+        custom_trace(&input, name, &message, "");
+    }
+
+    Ok(input)
+}
+
+/// Depth of a guarded parser which is restored even if the parser panics.
+#[cfg(any(feature = "trace", feature = "guard"))]
+struct GuardScope(usize);
+
+#[cfg(any(feature = "trace", feature = "guard"))]
+impl GuardScope {
+    fn enter() -> Self {
+        GuardScope(GUARD_DEPTH.with(|x| x.replace(x.get() + 1)))
+    }
+}
+
+#[cfg(any(feature = "trace", feature = "guard"))]
+impl Drop for GuardScope {
+    fn drop(&mut self) {
+        GUARD_DEPTH.with(|x| x.set(self.0));
+    }
+}
+
+/// Function to run the parser body with the depth check.
+/// This is inserted by `#[tracable_parser(guard)]` regardless of trace.
+///
+/// Without trace, the depth is checked only if `guard` feature is enabled.
+#[cfg(any(feature = "trace", feature = "guard"))]
+pub fn depth_guard<T, U, V, F>(input: T, name: &str, body: F) -> IResult<T, U, V>
+where
+    T: HasTracableInfo,
+    V: ParseError<T>,
+    F: FnOnce(T) -> IResult<T, U, V>,
+{
+    let info = input.get_tracable_info();
+    let depth = GUARD_DEPTH.with(|x| x.get());
+
+    if depth == 0 {
+        set_guard_error(None);
+    }
+
+    if depth >= info.max_depth {
+        let error = GuardError::DepthLimit {
+            parser: String::from(name),
+            max_depth: info.max_depth,
+        };
+        if info.depth_limit == DepthLimit::Print {
+            eprintln!("{}{}", error, call_stack());
+        }
+        set_guard_error(Some(error));
+        return Err(nom::Err::Failure(V::from_error_kind(
+            input,
+            DEPTH_LIMIT_ERROR,
+        )));
+    }
+
+    let _scope = GuardScope::enter();
+    body(input)
+}
+
+#[cfg(not(any(feature = "trace", feature = "guard")))]
+pub fn depth_guard<T, U, V, F>(input: T, _name: &str, body: F) -> IResult<T, U, V>
+where
+    T: HasTracableInfo,
+    V: ParseError<T>,
+    F: FnOnce(T) -> IResult<T, U, V>,
+{
+    body(input)
+}
+
+/// The current call stack like `\n  expr at offset 0` if trace is enabled.
+#[cfg(feature = "trace")]
+fn call_stack() -> String {
+    crate::TRACABLE_STORAGE.with(|storage| storage.borrow().call_stack())
+}
+
+#[cfg(all(not(feature = "trace"), feature = "guard"))]
+fn call_stack() -> String {
+    String::new()
+}
@@ -0,0 +1,56 @@
+use std::sync::RwLock;
+
+/// Table of interned values shared by all threads.
+///
+/// `TracableInfo` is `Copy` and may be moved to another thread,
+/// so it refers to non-`Copy` configuration by the id of this table.
+/// The id 0 means the default value.
+///
+/// The same value is interned once, but the values are never freed
+/// because the ids may be referred by any `TracableInfo`.
+/// The table is read by each traced parser, and is written only by the builders of `TracableInfo`.
+pub(crate) struct Interner<T> {
+    values: RwLock<Vec<T>>,
+}
+
+#[allow(dead_code)]
+impl<T: Clone + Default + PartialEq> Interner<T> {
+    pub(crate) const fn new() -> Self {
+        Interner {
+            values: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Apply `f` to the value of `id`, and return the id of the new value.
+    ///
+    /// The same id is returned for the same value.
+    pub(crate) fn update<F: FnOnce(&mut T)>(&self, id: usize, f: F) -> usize {
+        let mut values = self.values.write().unwrap_or_else(|x| x.into_inner());
+        let mut value = if id == 0 {
+            T::default()
+        } else {
+            values[id - 1].clone()
+        };
+        f(&mut value);
+
+        if value == T::default() {
+            0
+        } else if let Some(x) = values.iter().position(|x| *x == value) {
+            x + 1
+        } else {
+            values.push(value);
+            values.len()
+        }
+    }
+
+    /// Call `f` with the value of `id`.
+    ///
+    /// The table is not locked for the default value.
+    pub(crate) fn with<R, F: FnOnce(&T) -> R>(&self, id: usize, f: F) -> R {
+        if id == 0 {
+            f(&T::default())
+        } else {
+            f(&self.values.read().unwrap_or_else(|x| x.into_inner())[id - 1])
+        }
+    }
+}
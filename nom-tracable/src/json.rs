@@ -0,0 +1,121 @@
+use crate::{TracableInfo, TraceEvent, TraceSink};
+use std::fmt::Write as _;
+use std::io::Write;
+
+/// Sink to write trace events by JSON Lines format.
+///
+/// Each event is written as a JSON object in a line like below:
+///
+/// ```text
+/// {"event":"enter","name":"expr","depth":0,"forward":1,"offset":0,"line":1,"column":1,"fragment":"1+1"}
+/// {"event":"exit","name":"expr","depth":0,"backward":1,"offset":0,"line":1,"column":1,"result":"ok","consumed":3,"fragment":""}
+/// ```
+pub struct JsonLinesSink {
+    writer: Box<dyn Write>,
+}
+
+impl JsonLinesSink {
+    /// Create a sink writing to `writer`.
+    pub fn new<W: Write + 'static>(writer: W) -> Self {
+        JsonLinesSink {
+            writer: Box::new(writer),
+        }
+    }
+}
+
+impl TraceSink for JsonLinesSink {
+    fn event(&mut self, _info: &TracableInfo, event: &TraceEvent) {
+        let mut line = String::new();
+
+        let kind = match event {
+            TraceEvent::Enter { .. } => "enter",
+            TraceEvent::ExitOk { .. } | TraceEvent::ExitErr { .. } => "exit",
+            TraceEvent::Custom { .. } => "custom",
+        };
+        write!(
+            line,
+            "{{\"event\":\"{}\",\"name\":{},\"depth\":{}",
+            kind,
+            json_string(event.name()),
+            event.depth()
+        )
+        .unwrap();
+
+        match event {
+            TraceEvent::Enter { count, .. } => write!(line, ",\"forward\":{}", count).unwrap(),
+            TraceEvent::ExitOk { count, .. } | TraceEvent::ExitErr { count, .. } => {
+                write!(line, ",\"backward\":{}", count).unwrap()
+            }
+            TraceEvent::Custom { .. } => (),
+        }
+
+        if let Some(x) = event.offset() {
+            write!(line, ",\"offset\":{}", x).unwrap();
+        }
+        if let Some((l, c)) = event.location() {
+            write!(line, ",\"line\":{},\"column\":{}", l, c).unwrap();
+        }
+
+        match event {
+            TraceEvent::Enter { fragment, .. } => {
+                write!(line, ",\"fragment\":{}", json_string(fragment)).unwrap();
+            }
+            TraceEvent::ExitOk {
+                consumed, fragment, ..
+            } => {
+                line.push_str(",\"result\":\"ok\"");
+                if let Some(x) = consumed {
+                    write!(line, ",\"consumed\":{}", x).unwrap();
+                }
+                write!(line, ",\"fragment\":{}", json_string(fragment)).unwrap();
+            }
+            TraceEvent::ExitErr { kind, .. } => {
+                write!(line, ",\"result\":\"{}\"", kind.as_str()).unwrap();
+            }
+            TraceEvent::Custom { message, .. } => {
+                write!(line, ",\"message\":{}", json_string(message)).unwrap();
+            }
+        }
+        match event {
+            TraceEvent::ExitErr {
+                snippet: Some(x), ..
+            }
+            | TraceEvent::Custom {
+                snippet: Some(x), ..
+            } => {
+                write!(line, ",\"snippet\":{}", json_string(x)).unwrap();
+            }
+            _ => (),
+        }
+        line.push('}');
+
+        writeln!(self.writer, "{}", line).unwrap();
+
+        let finished = matches!(
+            event,
+            TraceEvent::ExitOk { depth: 0, .. } | TraceEvent::ExitErr { depth: 0, .. }
+        );
+        if finished {
+            self.writer.flush().unwrap();
+        }
+    }
+}
+
+/// Quote and escape `s` as JSON string.
+pub(crate) fn json_string(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len() + 2);
+    ret.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            '\t' => ret.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(ret, "\\u{:04x}", c as u32).unwrap(),
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
@@ -21,8 +21,7 @@
 //!     Ok((s, x.to_string()))
 //! }
 //!
-//! #[test]
-//! fn test() {
+//! fn main() {
 //!     // Configure trace setting
 //!     let info = TracableInfo::new().forward(true).backward(true);
 //!     let ret = term(LocatedSpan::new_extra("1", info));
@@ -30,11 +29,79 @@
 //! }
 //! ```
 
+pub use chrome::ChromeTraceSink;
+pub use event::{TraceErrorKind, TraceEvent};
+pub use failure::{FailureMode, FailureSink};
+#[cfg(feature = "trace")]
+use filter::TraceFilter;
+pub use folded::{FoldedStackSink, FoldedWeight};
+pub use graph::{CallEdge, CallGraph};
+#[cfg(feature = "trace")]
+pub use guard::guard_trace;
+pub use guard::{
+    depth_guard, guard_error, DepthLimit, GuardError, RecursionGuard, DEPTH_LIMIT_ERROR,
+    RECURSION_ERROR,
+};
+#[cfg(feature = "trace")]
+use intern::Interner;
+pub use json::JsonLinesSink;
+pub use memo::memo;
+#[cfg(feature = "trace")]
+pub use memo::memo_trace;
 #[cfg(feature = "trace")]
 use nom::IResult;
 /// Custom attribute to enable trace
+///
+/// The following arguments are available:
+///
+/// * `name = "..."`: the parser name shown in trace (the function name by default)
+/// * `fold`: fold the parser regardless of `TracableInfo::fold`
+/// * `silent`: count statistics without displaying trace of the parser
+/// * `memo`: memoize the successful result of the parser at each input offset regardless of trace.
+///   The output type must be `Clone + 'static`, and the input type must implement `Tracable`
+///   and `nom::Slice<RangeFrom<usize>>` even if trace is disabled.
+/// * `guard`: check `TracableInfo::recursion_guard` and `TracableInfo::max_depth` before running the parser.
+///   The error type must implement `nom::error::ParseError`.
+/// * `dynamic`: trace the parser only while `trace_enabled` returns `true`.
+///   The trace code is compiled in regardless of the feature of the crate using nom-tracable
+///   unless `cfg` is specified explicitly. `dynamic` feature of nom-tracable is required.
+/// * `cfg = "..."`: the feature name of the crate using nom-tracable to enable trace (`trace` by default).
+///
+/// ```ignore
+/// #[tracable_parser(name = "expression", fold)]
+/// pub fn expr(s: Span) -> IResult<Span, String> {
+///     alt((expr_plus, expr_minus, term))(s)
+/// }
+/// ```
 pub use nom_tracable_macros::tracable_parser;
-use std::{collections::HashMap, io::Write};
+pub use recorder::TraceRecorder;
+pub use reparse::Reparse;
+pub use report::FailureReport;
+pub use sink::{set_trace_sink, with_trace_sink, ColorMode, TableSink, TraceSink};
+pub use statistics::{ParseStatistics, ParserStatistics};
+#[cfg(feature = "trace")]
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+pub use theme::{Color, Style, Theme};
+
+mod chrome;
+mod env;
+mod event;
+mod failure;
+mod filter;
+mod folded;
+mod graph;
+mod guard;
+mod intern;
+mod json;
+mod memo;
+mod recorder;
+mod reparse;
+mod report;
+mod sink;
+mod statistics;
+mod theme;
 
 /// Trait to indicate the type can display as fragment.
 pub trait FragmentDisplay {
@@ -54,7 +121,7 @@ impl FragmentDisplay for &str {
     fn display(&self, width: usize) -> String {
         self.lines()
             .next()
-            .unwrap_or_else(|| "")
+            .unwrap_or("")
             .chars()
             .take(width)
             .collect()
@@ -67,6 +134,39 @@ pub trait Tracable: HasTracableInfo {
     fn dec_depth(self) -> Self;
     fn format(&self) -> String;
     fn header(&self) -> String;
+
+    /// Offset of the input from the beginning.
+    ///
+    /// The default implementation returns `None` for input types which don't track it.
+    /// Memo, reparse detection, recursion guard and furthest failure are skipped
+    /// for the input without offset.
+    fn offset(&self) -> Option<usize> {
+        None
+    }
+
+    /// Line and column of the input.
+    /// The default implementation returns `None` for input types which don't track them.
+    fn location(&self) -> Option<(u32, usize)> {
+        None
+    }
+
+    /// Fragment of the input to be displayed.
+    ///
+    /// The default implementation returns the column after the first ` : ` of `format`,
+    /// or the whole of `format` if it has no separator.
+    fn display_fragment(&self) -> String {
+        let format = self.format();
+        match format.split_once(" : ") {
+            Some((_, x)) => String::from(x),
+            None => format,
+        }
+    }
+
+    /// Source line of the input with a caret under the current position.
+    /// The default implementation returns `None` for input types which don't track lines.
+    fn snippet(&self) -> Option<String> {
+        None
+    }
 }
 
 /// Trait to indicate `TracableInfo` is provided.
@@ -87,7 +187,15 @@ pub struct TracableInfo {
     #[cfg(feature = "trace")]
     pub custom: bool,
     #[cfg(feature = "trace")]
-    pub color: bool,
+    pub color: ColorMode,
+    #[cfg(feature = "trace")]
+    pub snippet: bool,
+    #[cfg(feature = "trace")]
+    pub recursion_guard: RecursionGuard,
+    #[cfg(feature = "trace")]
+    pub reparse: bool,
+    #[cfg(any(feature = "trace", feature = "guard"))]
+    pub depth_limit: DepthLimit,
     #[cfg(feature = "trace")]
     pub count_width: usize,
     #[cfg(feature = "trace")]
@@ -95,9 +203,16 @@ pub struct TracableInfo {
     #[cfg(feature = "trace")]
     pub fragment_width: usize,
     #[cfg(feature = "trace")]
-    pub fold: u64,
+    pub fold: usize,
+    #[cfg(feature = "trace")]
+    pub max_trace_depth: usize,
+    #[cfg(any(feature = "trace", feature = "guard"))]
+    pub max_depth: usize,
+    #[cfg(feature = "trace")]
+    pub filter: usize,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for TracableInfo {
     fn default() -> Self {
         TracableInfo {
@@ -110,7 +225,15 @@ impl Default for TracableInfo {
             #[cfg(feature = "trace")]
             custom: true,
             #[cfg(feature = "trace")]
-            color: true,
+            color: ColorMode::Auto,
+            #[cfg(feature = "trace")]
+            snippet: false,
+            #[cfg(feature = "trace")]
+            recursion_guard: RecursionGuard::Off,
+            #[cfg(feature = "trace")]
+            reparse: false,
+            #[cfg(any(feature = "trace", feature = "guard"))]
+            depth_limit: DepthLimit::Failure,
             #[cfg(feature = "trace")]
             count_width: 10,
             #[cfg(feature = "trace")]
@@ -119,6 +242,12 @@ impl Default for TracableInfo {
             fragment_width: 96,
             #[cfg(feature = "trace")]
             fold: 0,
+            #[cfg(feature = "trace")]
+            max_trace_depth: usize::MAX,
+            #[cfg(any(feature = "trace", feature = "guard"))]
+            max_depth: usize::MAX,
+            #[cfg(feature = "trace")]
+            filter: 0,
         }
     }
 }
@@ -153,8 +282,40 @@ impl TracableInfo {
     }
 
     /// Set whether color is enabled.
-    pub fn color(mut self, x: bool) -> Self {
-        self.color = x;
+    ///
+    /// `bool` is accepted as `ColorMode::Always` or `ColorMode::Never`.
+    pub fn color<C: Into<ColorMode>>(mut self, x: C) -> Self {
+        self.color = x.into();
+        self
+    }
+
+    /// Set whether the source snippet is displayed at failures and custom trace.
+    ///
+    /// The snippet is the source line with a caret under the position like below:
+    ///
+    /// ```text
+    /// 3 | a = 1 +
+    ///   |        ^
+    /// ```
+    pub fn snippet(mut self, x: bool) -> Self {
+        self.snippet = x;
+        self
+    }
+
+    /// Set the behavior when a parser re-enters itself at the same offset.
+    ///
+    /// This detects left recursion which overflows the stack.
+    /// It is checked by the parsers with `#[tracable_parser(guard)]`.
+    pub fn recursion_guard(mut self, x: RecursionGuard) -> Self {
+        self.recursion_guard = x;
+        self
+    }
+
+    /// Set whether parsers invoked repeatedly at the same offset are recorded for `reparse`.
+    ///
+    /// This is disabled by default because the record grows with the number of parser calls.
+    pub fn reparse(mut self, x: bool) -> Self {
+        self.reparse = x;
         self
     }
 
@@ -177,26 +338,95 @@ impl TracableInfo {
     }
 
     /// Set the name of folding parser.
+    ///
+    /// Any number of parsers can be folded.
+    /// The set of folded parsers is shared by all threads,
+    /// so `TracableInfo` can be moved to another thread.
     pub fn fold(mut self, x: &str) -> Self {
-        let index =
-            crate::TRACABLE_STORAGE.with(|storage| storage.borrow_mut().get_parser_index(x));
+        self.fold = FOLD_SETS.update(self.fold, |set| {
+            set.insert(String::from(x));
+        });
+        self
+    }
 
-        let val = 1u64 << index;
-        let mask = !(1u64 << index);
+    /// Set the max depth of displayed trace.
+    ///
+    /// The parsers deeper than `x` are not displayed, but statistics are counted.
+    pub fn max_trace_depth(mut self, x: usize) -> Self {
+        self.max_trace_depth = x;
+        self
+    }
 
-        self.fold = (self.fold & mask) | val;
+    /// Set the min depth of displayed trace.
+    ///
+    /// The parsers shallower than `x` are not displayed, but statistics are counted.
+    pub fn min_trace_depth(mut self, x: usize) -> Self {
+        self.filter = FILTERS.update(self.filter, |f| f.min_depth = x);
         self
     }
 
-    fn folded(self, x: &str) -> bool {
-        let index =
-            crate::TRACABLE_STORAGE.with(|storage| storage.borrow_mut().get_parser_index(x));
+    /// Set the range of input offset in which trace is displayed.
+    ///
+    /// Parsers are displayed only if they are entered at the offset in `x`.
+    /// The statistics of the other parsers are counted.
+    /// The input without `Tracable::offset` is displayed regardless of the range.
+    ///
+    /// The filter is interned at the table shared by all threads, and is never freed.
+    /// Build `TracableInfo` once and reuse it rather than creating a new range for each parse.
+    pub fn offset_window(mut self, x: std::ops::Range<usize>) -> Self {
+        self.filter = FILTERS.update(self.filter, |f| f.offset = x);
+        self
+    }
 
-        if index < 64 {
-            ((self.fold >> index) & 1u64) == 1u64
-        } else {
-            false
+    /// Set the max number of displayed trace lines.
+    ///
+    /// No more parsers are displayed after `x` events of a top-level parser call,
+    /// but the exits of the displayed parsers are displayed to keep the call tree.
+    /// Statistics are counted.
+    pub fn max_trace_lines(mut self, x: usize) -> Self {
+        self.filter = FILTERS.update(self.filter, |f| f.max_lines = x);
+        self
+    }
+
+    /// Add the pattern of displayed parsers.
+    ///
+    /// If any pattern is added, only the parsers matched with the patterns are displayed.
+    /// The pattern is `|` separated glob patterns (e.g. `expr*|term`),
+    /// and `*` matches any string and `?` matches any character.
+    pub fn include(mut self, x: &str) -> Self {
+        self.filter = FILTERS.update(self.filter, |f| f.include.push(String::from(x)));
+        self
+    }
+
+    /// Add the pattern of hidden parsers.
+    ///
+    /// Unlike `fold`, the children of the hidden parsers are displayed.
+    /// The syntax of the pattern is the same as `include`.
+    pub fn exclude(mut self, x: &str) -> Self {
+        self.filter = FILTERS.update(self.filter, |f| f.exclude.push(String::from(x)));
+        self
+    }
+
+    fn visible(self, offset: Option<usize>) -> bool {
+        self.with_filter(|f, lines| f.visible(self.depth, offset, lines))
+    }
+
+    fn shown(self, name: &str, offset: Option<usize>) -> bool {
+        self.with_filter(|f, lines| f.visible(self.depth, offset, lines) && f.included(name))
+    }
+
+    /// Call `f` with the filter and the number of displayed lines.
+    /// The filter table is read once for all conditions.
+    fn with_filter<F: FnOnce(&TraceFilter, usize) -> bool>(self, f: F) -> bool {
+        if self.depth > self.max_trace_depth {
+            return false;
         }
+        let lines = crate::TRACABLE_STORAGE.with(|storage| storage.borrow().lines);
+        FILTERS.with(self.filter, |x| f(x, lines))
+    }
+
+    fn folded(self, x: &str) -> bool {
+        FOLD_SETS.with(self.fold, |set| set.contains(x))
     }
 }
 
@@ -218,7 +448,19 @@ impl TracableInfo {
         self
     }
 
-    pub fn color(self, _x: bool) -> Self {
+    pub fn color<C: Into<ColorMode>>(self, _x: C) -> Self {
+        self
+    }
+
+    pub fn snippet(self, _x: bool) -> Self {
+        self
+    }
+
+    pub fn recursion_guard(self, _x: RecursionGuard) -> Self {
+        self
+    }
+
+    pub fn reparse(self, _x: bool) -> Self {
         self
     }
 
@@ -237,6 +479,30 @@ impl TracableInfo {
     pub fn fold(self, _x: &str) -> Self {
         self
     }
+
+    pub fn max_trace_depth(self, _x: usize) -> Self {
+        self
+    }
+
+    pub fn min_trace_depth(self, _x: usize) -> Self {
+        self
+    }
+
+    pub fn offset_window(self, _x: std::ops::Range<usize>) -> Self {
+        self
+    }
+
+    pub fn max_trace_lines(self, _x: usize) -> Self {
+        self
+    }
+
+    pub fn include(self, _x: &str) -> Self {
+        self
+    }
+
+    pub fn exclude(self, _x: &str) -> Self {
+        self
+    }
 }
 
 impl HasTracableInfo for TracableInfo {
@@ -249,7 +515,6 @@ impl HasTracableInfo for TracableInfo {
     }
 }
 
-#[cfg(feature = "trace")]
 impl<T, U: HasTracableInfo> HasTracableInfo for nom_locate::LocatedSpan<T, U> {
     fn get_tracable_info(&self) -> TracableInfo {
         self.extra.get_tracable_info()
@@ -261,42 +526,118 @@ impl<T, U: HasTracableInfo> HasTracableInfo for nom_locate::LocatedSpan<T, U> {
     }
 }
 
-#[cfg(feature = "trace")]
+// This is implemented regardless of trace because `#[tracable_parser(memo)]` requires it.
 impl<T: FragmentDisplay + nom::AsBytes, U: HasTracableInfo> Tracable
     for nom_locate::LocatedSpan<T, U>
 {
+    #[cfg(feature = "trace")]
     fn inc_depth(self) -> Self {
         let info = self.get_tracable_info();
         let info = info.depth(info.depth + 1);
         self.set_tracable_info(info)
     }
 
+    #[cfg(not(feature = "trace"))]
+    fn inc_depth(self) -> Self {
+        self
+    }
+
+    #[cfg(feature = "trace")]
     fn dec_depth(self) -> Self {
         let info = self.get_tracable_info();
         let info = info.depth(info.depth - 1);
         self.set_tracable_info(info)
     }
 
+    #[cfg(not(feature = "trace"))]
+    fn dec_depth(self) -> Self {
+        self
+    }
+
+    fn offset(&self) -> Option<usize> {
+        Some(self.location_offset())
+    }
+
     fn format(&self) -> String {
-        let info = self.get_tracable_info();
-        let fragment = self.fragment().display(info.fragment_width);
-        format!("{:<8} : {}", self.location_offset(), fragment)
+        format!(
+            "{:<8} : {}",
+            self.location_offset(),
+            self.display_fragment()
+        )
     }
 
     fn header(&self) -> String {
         format!("{:<8} : {}", "offset", "fragment")
     }
+
+    fn location(&self) -> Option<(u32, usize)> {
+        Some((self.location_line(), self.get_column()))
+    }
+
+    #[cfg(feature = "trace")]
+    fn display_fragment(&self) -> String {
+        let info = self.get_tracable_info();
+        self.fragment().display(info.fragment_width)
+    }
+
+    #[cfg(not(feature = "trace"))]
+    fn display_fragment(&self) -> String {
+        self.fragment().display(usize::MAX)
+    }
+
+    fn snippet(&self) -> Option<String> {
+        let line = String::from_utf8_lossy(self.get_line_beginning());
+        let number = self.location_line().to_string();
+        Some(format!(
+            "{} | {}\n{} | {}^",
+            number,
+            line.trim_end_matches('\r'),
+            " ".repeat(number.len()),
+            " ".repeat(self.get_utf8_column() - 1)
+        ))
+    }
 }
 
 #[derive(Debug, Default)]
 struct TracableStorage {
     forward_count: usize,
     backward_count: usize,
-    parser_indexes: HashMap<String, usize>,
-    parser_index_next: usize,
     histogram: HashMap<String, usize>,
     cumulative_histogram: HashMap<String, usize>,
     cumulative_working: HashMap<(String, usize), usize>,
+    frames: Vec<Frame>,
+    epoch: Option<Instant>,
+    total_time: HashMap<String, Duration>,
+    self_time: HashMap<String, Duration>,
+    success: HashMap<String, usize>,
+    failure: HashMap<String, usize>,
+    max_depth: usize,
+    lines: usize,
+    sink_time: Duration,
+    furthest_failure: FailureReport,
+    /// Parsers failed at the offset of `furthest_failure`
+    failed: Vec<&'static str>,
+    calls_at: HashMap<(&'static str, usize, &'static str), usize>,
+    memo_hits: HashMap<String, usize>,
+    memo_misses: HashMap<String, usize>,
+    /// Call count and success count of the edges from caller to callee
+    edges: HashMap<(&'static str, &'static str), (usize, usize)>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug)]
+struct Frame {
+    name: &'static str,
+    offset: Option<usize>,
+    location: Option<(u32, usize)>,
+    snippet: Option<String>,
+    shown: bool,
+    start: Instant,
+    sink_time: Duration,
+    failure_mark: usize,
+    children: Duration,
+    folded: bool,
+    options: ParserOptions,
 }
 
 #[allow(dead_code)]
@@ -311,6 +652,19 @@ impl TracableStorage {
         self.histogram.clear();
         self.cumulative_histogram.clear();
         self.cumulative_working.clear();
+        self.frames.clear();
+        self.total_time.clear();
+        self.self_time.clear();
+        self.success.clear();
+        self.failure.clear();
+        self.max_depth = 0;
+        self.lines = 0;
+        self.furthest_failure = FailureReport::default();
+        self.failed.clear();
+        self.calls_at.clear();
+        self.memo_hits.clear();
+        self.memo_misses.clear();
+        self.edges.clear();
     }
 
     fn get_forward_count(&self) -> usize {
@@ -329,6 +683,118 @@ impl TracableStorage {
         self.backward_count += 1
     }
 
+    fn add_call_at(&mut self, key: &'static str, offset: usize) {
+        let caller = self.caller();
+        *self.calls_at.entry((key, offset, caller)).or_insert(0) += 1;
+    }
+
+    /// The name of the innermost active parser, or empty string at the top level.
+    fn caller(&self) -> &'static str {
+        self.frames.last().map(|x| x.name).unwrap_or_default()
+    }
+
+    /// The number of the parsers failed at `offset` so far.
+    fn failure_mark(&self, offset: Option<usize>) -> usize {
+        if !self.failed.is_empty() && offset == Some(self.furthest_failure.offset) {
+            self.failed.len()
+        } else {
+            0
+        }
+    }
+
+    /// Record the failure of the parser of `frame` at `offset`.
+    ///
+    /// The parsers failed at the same offset after the parser was entered are its descendants.
+    /// If there is only one, the parser replaces it because the parser is more meaningful for users.
+    /// If there are several, the parser is omitted because it encloses the failed alternatives.
+    fn update_failure(&mut self, frame: &Frame, offset: usize) {
+        if self.failed.is_empty() || offset > self.furthest_failure.offset {
+            self.furthest_failure.offset = offset;
+            self.furthest_failure.location = frame.location;
+            self.furthest_failure.snippet = frame.snippet.clone();
+            self.failed.clear();
+        }
+        if offset == self.furthest_failure.offset {
+            match self.failed.len().saturating_sub(frame.failure_mark) {
+                0 => self.failed.push(frame.name),
+                1 => *self.failed.last_mut().unwrap() = frame.name,
+                _ => (),
+            }
+        }
+    }
+
+    fn furthest_failure(&self) -> Option<FailureReport> {
+        if self.failed.is_empty() {
+            return None;
+        }
+        let mut report = self.furthest_failure.clone();
+        for name in &self.failed {
+            if report.expected.iter().all(|x| x != name) {
+                report.expected.push(String::from(*name));
+            }
+        }
+        Some(report)
+    }
+
+    /// The call count and success count of the edge from the innermost active parser to `key`.
+    fn edge_mut(&mut self, key: &'static str) -> &mut (usize, usize) {
+        let caller = self.caller();
+        self.edges.entry((caller, key)).or_default()
+    }
+
+    fn call_graph(&self) -> CallGraph {
+        let mut ret = CallGraph::default();
+        for ((caller, callee), (calls, success)) in &self.edges {
+            if caller.is_empty() {
+                ret.roots.insert(String::from(*callee));
+            } else {
+                ret.edges.push(CallEdge {
+                    caller: String::from(*caller),
+                    callee: String::from(*callee),
+                    calls: *calls,
+                    success: *success,
+                });
+            }
+        }
+        ret.edges
+            .sort_by(|a, b| a.caller.cmp(&b.caller).then(a.callee.cmp(&b.callee)));
+        ret
+    }
+
+    fn reparse(&self) -> Vec<Reparse> {
+        let mut calls: HashMap<(&str, usize), usize> = HashMap::new();
+        for ((name, offset, _), count) in &self.calls_at {
+            *calls.entry((name, *offset)).or_insert(0) += count;
+        }
+
+        let mut map: HashMap<&str, Reparse> = HashMap::new();
+        for ((name, _), calls) in calls.iter().filter(|(_, x)| **x >= 2) {
+            let entry = map.entry(name).or_insert_with(|| Reparse {
+                name: String::from(*name),
+                ..Default::default()
+            });
+            entry.redundant_calls += calls - 1;
+            entry.offsets += 1;
+            entry.max_calls = entry.max_calls.max(*calls);
+        }
+        for ((name, offset, caller), count) in &self.calls_at {
+            if caller.is_empty() || calls[&(*name, *offset)] < 2 {
+                continue;
+            }
+            if let Some(entry) = map.get_mut(name) {
+                *entry.callers.entry(String::from(*caller)).or_insert(0) += count;
+            }
+        }
+
+        let mut ret: Vec<_> = map.into_values().collect();
+        ret.sort_by(|a, b| {
+            b.redundant_calls
+                .cmp(&a.redundant_calls)
+                .then(a.name.cmp(&b.name))
+        });
+        ret
+    }
+
     fn inc_histogram(&mut self, key: &str) {
         let next = if let Some(x) = self.histogram.get(key) {
             x + 1
@@ -347,6 +813,41 @@ impl TracableStorage {
         self.cumulative_histogram.insert(String::from(key), next);
     }
 
+    fn inc_result(&mut self, key: &str, success: bool) {
+        let map = if success {
+            &mut self.success
+        } else {
+            &mut self.failure
+        };
+        *map.entry(String::from(key)).or_insert(0) += 1;
+    }
+
+    fn update_max_depth(&mut self, depth: usize) {
+        self.max_depth = self.max_depth.max(depth);
+    }
+
+    fn statistics(&self) -> ParseStatistics {
+        let mut ret = ParseStatistics {
+            max_depth: self.max_depth,
+            ..Default::default()
+        };
+        for (name, calls) in &self.histogram {
+            let get = |map: &HashMap<String, usize>| map.get(name).copied().unwrap_or(0);
+            let stat = ParserStatistics {
+                calls: *calls,
+                cumulative_calls: get(&self.cumulative_histogram),
+                success: get(&self.success),
+                failure: get(&self.failure),
+                total_time: self.total_time.get(name).copied().unwrap_or_default(),
+                self_time: self.self_time.get(name).copied().unwrap_or_default(),
+                memo_hits: get(&self.memo_hits),
+                memo_misses: get(&self.memo_misses),
+            };
+            ret.parsers.insert(name.clone(), stat);
+        }
+        ret
+    }
+
     fn add_cumulative(&mut self, key: &str, depth: usize) {
         self.cumulative_working
             .insert((String::from(key), depth), 0);
@@ -354,7 +855,7 @@ impl TracableStorage {
 
     fn inc_cumulative(&mut self) {
         for val in self.cumulative_working.values_mut() {
-            *val = *val + 1;
+            *val += 1;
         }
     }
 
@@ -366,18 +867,65 @@ impl TracableStorage {
         self.cumulative_working.get(&(key.to_string(), depth))
     }
 
-    fn get_parser_index(&mut self, key: &str) -> usize {
-        if let Some(x) = self.parser_indexes.get(key) {
-            *x
-        } else {
-            let new_index = self.parser_index_next;
-            self.parser_index_next += 1;
-            self.parser_indexes.insert(String::from(key), new_index);
-            new_index
+    /// Elapsed time from the first trace of the thread.
+    fn elapsed(&mut self) -> Duration {
+        self.epoch.get_or_insert_with(Instant::now).elapsed()
+    }
+
+    /// The cycle of active parsers if `key` is re-entered at `offset`.
+    ///
+    /// The frame of `key` itself must be pushed already.
+    fn recursion_cycle(&self, key: &str, offset: usize) -> Option<String> {
+        let active = &self.frames[..self.frames.len().saturating_sub(1)];
+        let start = active
+            .iter()
+            .rposition(|x| x.name == key && x.offset == Some(offset))?;
+        let cycle: Vec<_> = self.frames[start..].iter().map(|x| x.name).collect();
+        Some(cycle.join(" -> "))
+    }
+
+    /// The names and offsets of active parsers from the outermost.
+    fn call_stack(&self) -> String {
+        self.frames
+            .iter()
+            .map(|x| match x.offset {
+                Some(offset) => format!("\n  {} at offset {}", x.name, offset),
+                None => format!("\n  {}", x.name),
+            })
+            .collect()
+    }
+
+    fn pop_frame(&mut self) -> Option<Frame> {
+        let frame = self.frames.pop()?;
+
+        // The time spent by the sink for the events of children is excluded
+        let sink_time = self.sink_time.saturating_sub(frame.sink_time);
+        let elapsed = frame.start.elapsed().saturating_sub(sink_time);
+
+        *self.self_time.entry(String::from(frame.name)).or_default() +=
+            elapsed.saturating_sub(frame.children);
+
+        // The elapsed time of recursive call is included in the outermost call
+        if self.frames.iter().all(|x| x.name != frame.name) {
+            *self.total_time.entry(String::from(frame.name)).or_default() += elapsed;
+        }
+
+        if let Some(parent) = self.frames.last_mut() {
+            parent.children += elapsed;
         }
+
+        Some(frame)
     }
 }
 
+/// Sets of folded parsers referred by `TracableInfo::fold`.
+#[cfg(feature = "trace")]
+static FOLD_SETS: Interner<BTreeSet<String>> = Interner::new();
+
+/// Filters of displayed trace referred by `TracableInfo::filter`.
+#[cfg(feature = "trace")]
+static FILTERS: Interner<TraceFilter> = Interner::new();
+
 #[cfg(feature = "trace")]
 thread_local!(
     static TRACABLE_STORAGE: core::cell::RefCell<crate::TracableStorage> = {
@@ -395,7 +943,7 @@ thread_local!(
 /// # use nom::character::complete::*;
 /// # use nom::IResult;
 /// # use nom_locate::LocatedSpan;
-/// # use nom_tracable::{cumulative_histogram, tracable_parser, TracableInfo};
+/// # use nom_tracable::{histogram, tracable_parser, TracableInfo};
 /// #
 /// # type Span<'a> = LocatedSpan<&'a str, TracableInfo>;
 /// #
@@ -405,8 +953,7 @@ thread_local!(
 /// #     Ok((s, x.to_string()))
 /// # }
 /// #
-/// # #[test]
-/// # fn test() {
+/// # fn main() {
 ///     let ret = term(LocatedSpan::new_extra("1", TracableInfo::new()));
 ///     histogram(); // Show histogram of "1" parsing
 ///
@@ -451,8 +998,7 @@ fn histogram_internal() {}
 /// #     Ok((s, x.to_string()))
 /// # }
 /// #
-/// # #[test]
-/// # fn test() {
+/// # fn main() {
 ///     let ret = term(LocatedSpan::new_extra("1", TracableInfo::new()));
 ///     cumulative_histogram(); // Show cumulative histogram of "1" parsing
 ///
@@ -475,170 +1021,431 @@ fn cumulative_histogram_internal() {
 #[cfg(not(feature = "trace"))]
 fn cumulative_histogram_internal() {}
 
-#[allow(dead_code)]
+/// Show histogram of parser elapsed time.
+///
+/// The total time includes the time of children parsers, and the self time doesn't.
+/// The time spent by the trace sink to output events is excluded from both.
+/// The parsers are ranked by the self time.
+///
+/// The statistics information to generate histogram is reset at each parser call.
+/// Therefore `timing_histogram` should be called before next parser call.
+/// The information is thread independent because it is stored at thread local storage.
+///
+/// ```
+/// # use nom::character::complete::*;
+/// # use nom::IResult;
+/// # use nom_locate::LocatedSpan;
+/// # use nom_tracable::{timing_histogram, tracable_parser, TracableInfo};
+/// #
+/// # type Span<'a> = LocatedSpan<&'a str, TracableInfo>;
+/// #
+/// # #[tracable_parser]
+/// # pub fn term(s: Span) -> IResult<Span, String> {
+/// #     let (s, x) = char('1')(s)?;
+/// #     Ok((s, x.to_string()))
+/// # }
+/// #
+/// # fn main() {
+///     let ret = term(LocatedSpan::new_extra("1", TracableInfo::new()));
+///     timing_histogram(); // Show timing histogram of "1" parsing
+/// # }
+/// ```
+pub fn timing_histogram() {
+    timing_histogram_internal();
+}
+
+#[cfg(feature = "trace")]
+fn timing_histogram_internal() {
+    crate::TRACABLE_STORAGE.with(|storage| {
+        let storage = storage.borrow();
+        let mut result: Vec<_> = storage
+            .self_time
+            .iter()
+            .map(|(p, s)| {
+                let t = storage.total_time.get(p).copied().unwrap_or_default();
+                (p.as_str(), t, *s)
+            })
+            .collect();
+        result.sort_by(|a, b| b.2.cmp(&a.2).then(a.0.cmp(b.0)));
+
+        crate::sink::with_sink(|sink| sink.timing_histogram(&result));
+    });
+}
+
+#[cfg(not(feature = "trace"))]
+fn timing_histogram_internal() {}
+
+/// Get statistics of parser calls.
+///
+/// This returns the information shown by `histogram`, `cumulative_histogram` and `timing_histogram` as value.
+///
+/// The statistics information is reset at each parser call.
+/// Therefore `statistics` should be called before next parser call.
+/// The information is thread independent because it is stored at thread local storage.
+///
+/// ```
+/// # use nom::character::complete::*;
+/// # use nom::IResult;
+/// # use nom_locate::LocatedSpan;
+/// # use nom_tracable::{statistics, tracable_parser, TracableInfo};
+/// #
+/// # type Span<'a> = LocatedSpan<&'a str, TracableInfo>;
+/// #
+/// # #[tracable_parser]
+/// # pub fn term(s: Span) -> IResult<Span, String> {
+/// #     let (s, x) = char('1')(s)?;
+/// #     Ok((s, x.to_string()))
+/// # }
+/// #
+/// # fn main() {
+///     let ret = term(LocatedSpan::new_extra("1", TracableInfo::new()));
+///     let stats = statistics();
+///     if nom_tracable::trace_enabled() {
+///         assert_eq!(stats.parser("term").unwrap().success, 1);
+///     }
+/// # }
+/// ```
+pub fn statistics() -> ParseStatistics {
+    statistics_internal()
+}
+
+#[cfg(feature = "trace")]
+fn statistics_internal() -> ParseStatistics {
+    crate::TRACABLE_STORAGE.with(|storage| storage.borrow().statistics())
+}
+
+#[cfg(not(feature = "trace"))]
+fn statistics_internal() -> ParseStatistics {
+    ParseStatistics::default()
+}
+
+/// Get the report of the furthest failure.
+///
+/// This returns the parsers failed at the furthest input offset in the last parse,
+/// and can be used as the error message like `at line 1, col 5: expected one of expr_plus, expr_minus, term`.
+/// Silent parsers are not included.
+/// If no parser failed, this returns `None`.
+///
+/// The report is reset at each parser call like `statistics`.
+///
+/// ```
+/// # use nom::character::complete::*;
+/// # use nom::IResult;
+/// # use nom_locate::LocatedSpan;
+/// # use nom_tracable::{furthest_failure, tracable_parser, TracableInfo};
+/// #
+/// # type Span<'a> = LocatedSpan<&'a str, TracableInfo>;
+/// #
+/// # #[tracable_parser]
+/// # pub fn term(s: Span) -> IResult<Span, String> {
+/// #     let (s, x) = char('1')(s)?;
+/// #     Ok((s, x.to_string()))
+/// # }
+/// #
+/// # fn main() {
+///     let ret = term(LocatedSpan::new_extra("2", TracableInfo::new()));
+///     if nom_tracable::trace_enabled() {
+///         let report = furthest_failure().unwrap();
+///         assert_eq!(report.to_string(), "at line 1, col 1: expected term");
+///     }
+/// # }
+/// ```
+pub fn furthest_failure() -> Option<FailureReport> {
+    furthest_failure_internal()
+}
+
+#[cfg(feature = "trace")]
+fn furthest_failure_internal() -> Option<FailureReport> {
+    crate::TRACABLE_STORAGE.with(|storage| storage.borrow().furthest_failure())
+}
+
+#[cfg(not(feature = "trace"))]
+fn furthest_failure_internal() -> Option<FailureReport> {
+    None
+}
+
+/// Show histogram of memo hit count of parsers with `#[tracable_parser(memo)]`.
+///
+/// The statistics information to generate histogram is reset at each parser call.
+/// Therefore `memo_histogram` should be called before next parser call.
+pub fn memo_histogram() {
+    memo_histogram_internal();
+}
+
+#[cfg(feature = "trace")]
+fn memo_histogram_internal() {
+    crate::TRACABLE_STORAGE.with(|storage| {
+        let storage = storage.borrow();
+        show_histogram("memo histogram", &storage.memo_hits);
+    });
+}
+
+#[cfg(not(feature = "trace"))]
+fn memo_histogram_internal() {}
+
+/// Get parsers invoked repeatedly at the same offset.
+///
+/// The result is sorted by the redundant call count in descending order,
+/// and can be used to find candidates of memoization.
+/// The calls are recorded only if `TracableInfo::reparse` is enabled,
+/// and the input has `Tracable::offset`.
+///
+/// The information is reset at each parser call like `statistics`.
+///
+/// ```
+/// # use nom::branch::*;
+/// # use nom::character::complete::*;
+/// # use nom::IResult;
+/// # use nom_locate::LocatedSpan;
+/// # use nom_tracable::{reparse, tracable_parser, TracableInfo};
+/// #
+/// # type Span<'a> = LocatedSpan<&'a str, TracableInfo>;
+/// #
+/// # #[tracable_parser]
+/// # pub fn term(s: Span) -> IResult<Span, String> {
+/// #     let (s, x) = char('1')(s)?;
+/// #     Ok((s, x.to_string()))
+/// # }
+/// #
+/// # #[tracable_parser]
+/// # pub fn expr(s: Span) -> IResult<Span, String> {
+/// #     alt((
+/// #         |s| { let (s, x) = term(s)?; let (s, _) = char('+')(s)?; Ok((s, x)) },
+/// #         term,
+/// #     ))(s)
+/// # }
+/// #
+/// # fn main() {
+///     let ret = expr(LocatedSpan::new_extra("1", TracableInfo::new().reparse(true)));
+///     if nom_tracable::trace_enabled() {
+///         let reparse = reparse();
+///         assert_eq!(reparse[0].name, "term");
+///         assert_eq!(reparse[0].redundant_calls, 1);
+///     }
+/// # }
+/// ```
+pub fn reparse() -> Vec<Reparse> {
+    reparse_internal()
+}
+
+#[cfg(feature = "trace")]
+fn reparse_internal() -> Vec<Reparse> {
+    crate::TRACABLE_STORAGE.with(|storage| storage.borrow().reparse())
+}
+
+#[cfg(not(feature = "trace"))]
+fn reparse_internal() -> Vec<Reparse> {
+    Vec::new()
+}
+
+/// Get the call graph of parsers.
+///
+/// The edges from caller to callee are recorded with call count and success count,
+/// and can be exported by `CallGraph::to_dot` or `CallGraph::to_mermaid`.
+///
+/// The information is reset at each parser call like `statistics`.
+///
+/// ```
+/// # use nom::character::complete::*;
+/// # use nom::IResult;
+/// # use nom_locate::LocatedSpan;
+/// # use nom_tracable::{call_graph, tracable_parser, TracableInfo};
+/// #
+/// # type Span<'a> = LocatedSpan<&'a str, TracableInfo>;
+/// #
+/// # #[tracable_parser]
+/// # pub fn term(s: Span) -> IResult<Span, String> {
+/// #     let (s, x) = char('1')(s)?;
+/// #     Ok((s, x.to_string()))
+/// # }
+/// #
+/// # #[tracable_parser]
+/// # pub fn expr(s: Span) -> IResult<Span, String> {
+/// #     term(s)
+/// # }
+/// #
+/// # fn main() {
+///     let ret = expr(LocatedSpan::new_extra("1", TracableInfo::new()));
+///     if nom_tracable::trace_enabled() {
+///         let graph = call_graph();
+///         assert_eq!(graph.edge("expr", "term").unwrap().calls, 1);
+///         assert!(graph.to_dot().contains("\"expr\" -> \"term\""));
+///     }
+/// # }
+/// ```
+pub fn call_graph() -> CallGraph {
+    call_graph_internal()
+}
+
+#[cfg(feature = "trace")]
+fn call_graph_internal() -> CallGraph {
+    crate::TRACABLE_STORAGE.with(|storage| storage.borrow().call_graph())
+}
+
+#[cfg(not(feature = "trace"))]
+fn call_graph_internal() -> CallGraph {
+    CallGraph::default()
+}
+
+/// Show histogram of redundant call count of parsers invoked repeatedly at the same offset.
+///
+/// See `reparse` for the detail.
+pub fn reparse_histogram() {
+    reparse_histogram_internal();
+}
+
+#[cfg(feature = "trace")]
+fn reparse_histogram_internal() {
+    let reparse = reparse_internal();
+    let result: Vec<_> = reparse
+        .iter()
+        .map(|x| (x.name.as_str(), x.redundant_calls))
+        .collect();
+    crate::sink::with_sink(|sink| sink.histogram("reparse histogram", &result));
+}
+
+#[cfg(not(feature = "trace"))]
+fn reparse_histogram_internal() {}
+
+#[cfg(feature = "trace")]
 fn show_histogram(title: &str, map: &HashMap<String, usize>) {
-    let mut result = Vec::new();
-    let mut max_parser_len = "parser".len();
-    let mut max_count = 0;
-    let mut max_count_len = "count".len();
-    for (p, c) in map {
-        result.push((p, c));
-        max_parser_len = max_parser_len.max(p.len());
-        max_count = max_count.max(*c);
-        max_count_len = max_count_len.max(format!("{}", c).len());
-    }
+    let mut result: Vec<_> = map.iter().map(|(p, c)| (p.as_str(), *c)).collect();
+    result.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
 
-    result.sort_by(|a, b| b.1.partial_cmp(a.1).unwrap());
+    crate::sink::with_sink(|sink| sink.histogram(title, &result));
+}
 
-    let bar_length = 50;
+#[cfg(feature = "dynamic")]
+static TRACE_ENABLED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
 
-    let mut lock = if cfg!(feature = "stderr") {
-        Box::new(std::io::stderr().lock()) as Box<dyn Write>
-    } else {
-        Box::new(std::io::stdout().lock()) as Box<dyn Write>
-    };
+/// Set whether trace is enabled at runtime.
+///
+/// This is effective only if `dynamic` feature is enabled.
+/// In `dynamic` feature, the parsers with `#[tracable_parser(dynamic)]` are not traced
+/// until `set_trace_enabled(true)` is called.
+/// The setting is shared by all threads.
+pub fn set_trace_enabled(x: bool) {
+    set_trace_enabled_internal(x);
+}
 
-    writeln!(
-        lock,
-        "\n{:<parser$} | {:<bar$} | {}",
-        "parser",
-        title,
-        "count",
-        parser = max_parser_len,
-        bar = bar_length,
-    )
-    .unwrap();
-
-    writeln!(
-        lock,
-        "{:<parser$} | {:<bar$} | {}",
-        "-".repeat(max_parser_len),
-        "-".repeat(bar_length),
-        "-".repeat(max_count_len),
-        parser = max_parser_len,
-        bar = bar_length,
-    )
-    .unwrap();
-
-    for (p, c) in &result {
-        let bar = *c * bar_length / max_count;
-        if bar > 0 {
-            writeln!(
-                lock,
-                "{:<parser$} | {}{} | {}",
-                p,
-                ".".repeat(bar),
-                " ".repeat(bar_length - bar),
-                c,
-                parser = max_parser_len,
-            )
-            .unwrap();
-        }
-    }
-    writeln!(lock, "").unwrap()
+#[cfg(feature = "dynamic")]
+fn set_trace_enabled_internal(x: bool) {
+    TRACE_ENABLED.store(x, std::sync::atomic::Ordering::Relaxed);
+}
+
+#[cfg(not(feature = "dynamic"))]
+fn set_trace_enabled_internal(_x: bool) {}
+
+/// Get whether trace is enabled.
+///
+/// This returns whether `trace` feature is enabled,
+/// or the value set by `set_trace_enabled` if `dynamic` feature is enabled.
+/// This is inserted by `#[tracable_parser(dynamic)]`.
+#[inline]
+pub fn trace_enabled() -> bool {
+    trace_enabled_internal()
+}
+
+#[cfg(feature = "dynamic")]
+#[inline]
+fn trace_enabled_internal() -> bool {
+    TRACE_ENABLED.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+#[cfg(not(feature = "dynamic"))]
+#[inline]
+fn trace_enabled_internal() -> bool {
+    cfg!(feature = "trace")
+}
+
+/// Options of parser given by the arguments of `#[tracable_parser]`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParserOptions {
+    /// Fold the parser regardless of `TracableInfo::fold`
+    pub fold: bool,
+    /// Count statistics without displaying trace of the parser
+    pub silent: bool,
 }
 
 /// Function to display forward trace.
+#[cfg(feature = "trace")]
+pub fn forward_trace<T: Tracable>(input: T, name: &'static str) -> (TracableInfo, T) {
+    forward_trace_with(input, name, ParserOptions::default())
+}
+
+/// Function to display forward trace with parser options.
 /// This is inserted by `#[tracable_parser]`.
 #[cfg(feature = "trace")]
-pub fn forward_trace<T: Tracable>(input: T, name: &str) -> (TracableInfo, T) {
+pub fn forward_trace_with<T: Tracable>(
+    input: T,
+    name: &'static str,
+    options: ParserOptions,
+) -> (TracableInfo, T) {
     let info = input.get_tracable_info();
     let depth = info.depth;
-
-    let mut lock = if cfg!(feature = "stderr") {
-        Box::new(std::io::stderr().lock()) as Box<dyn Write>
-    } else {
-        Box::new(std::io::stdout().lock()) as Box<dyn Write>
-    };
+    let folded = options.fold || info.folded(name);
 
     if depth == 0 {
         crate::TRACABLE_STORAGE.with(|storage| {
             storage.borrow_mut().init();
         });
-        let forward_backword = if info.forward & info.backward {
-            format!(
-                "{:<count_width$} {:<count_width$}",
-                "forward",
-                "backward",
-                count_width = info.count_width
-            )
-        } else if info.forward {
-            format!(
-                "{:<count_width$}",
-                "forward",
-                count_width = info.count_width
-            )
-        } else {
-            format!(
-                "{:<count_width$}",
-                "backward",
-                count_width = info.count_width
-            )
-        };
-
-        let control_witdh = if info.color { 11 } else { 0 };
-
-        writeln!(
-            lock,
-            "\n{} : {:<parser_width$} : {}",
-            forward_backword,
-            "parser",
-            input.header(),
-            parser_width = info.parser_width - control_witdh,
-        )
-        .unwrap();
+        crate::sink::with_sink(|sink| sink.header(&info, &input.header()));
     }
 
-    if info.forward {
+    // The exit is displayed if and only if the enter is displayed
+    // so that sinks can reconstruct the call tree even if trace lines are limited.
+    let offset = input.offset();
+    let shown = !options.silent && info.shown(name, offset);
+
+    if info.forward && shown {
         let forward_count = crate::TRACABLE_STORAGE.with(|storage| {
             storage.borrow_mut().inc_forward_count();
             storage.borrow().get_forward_count()
         });
 
-        let forward_backword = if info.backward {
-            format!(
-                "{:<count_width$} {:<count_width$}",
-                forward_count,
-                "",
-                count_width = info.count_width
-            )
-        } else {
-            format!(
-                "{:<count_width$}",
-                forward_count,
-                count_width = info.count_width
-            )
+        let timestamp = crate::TRACABLE_STORAGE.with(|storage| storage.borrow_mut().elapsed());
+        let event = TraceEvent::Enter {
+            name: String::from(name),
+            depth,
+            count: forward_count,
+            offset,
+            location: input.location(),
+            folded,
+            fragment: input.display_fragment(),
+            input: input.format(),
+            timestamp,
         };
-
-        let color = if info.color { "\u{001b}[1;37m" } else { "" };
-        let reset = if info.color { "\u{001b}[0m" } else { "" };
-        let folded = if info.folded(name) { "+" } else { " " };
-
-        writeln!(
-            lock,
-            "{} : {:<parser_width$} : {}",
-            forward_backword,
-            format!(
-                "{}{}-> {} {}{}",
-                color,
-                " ".repeat(depth),
-                name,
-                folded,
-                reset
-            ),
-            input.format(),
-            parser_width = info.parser_width,
-        )
-        .unwrap();
+        emit_event(&info, &event);
     }
 
+    let snippet = if info.snippet { input.snippet() } else { None };
     crate::TRACABLE_STORAGE.with(|storage| {
+        if let Some(offset) = offset.filter(|_| info.reparse) {
+            storage.borrow_mut().add_call_at(name, offset);
+        }
+        storage.borrow_mut().edge_mut(name).0 += 1;
+        let sink_time = storage.borrow().sink_time;
+        let failure_mark = storage.borrow().failure_mark(offset);
+        storage.borrow_mut().frames.push(Frame {
+            name,
+            offset,
+            location: input.location(),
+            snippet,
+            shown,
+            start: Instant::now(),
+            sink_time,
+            failure_mark,
+            children: Duration::default(),
+            folded,
+            options,
+        });
         storage.borrow_mut().inc_histogram(name);
+        storage.borrow_mut().update_max_depth(depth);
         storage.borrow_mut().add_cumulative(name, depth);
         storage.borrow_mut().inc_cumulative();
     });
 
-    let input = if info.folded(name) {
+    let input = if folded {
         let info = info.forward(false).backward(false).custom(false);
         input.set_tracable_info(info)
     } else {
@@ -651,6 +1458,8 @@ pub fn forward_trace<T: Tracable>(input: T, name: &str) -> (TracableInfo, T) {
 
 /// Function to display backward trace.
 /// This is inserted by `#[tracable_parser]`.
+///
+/// The parser options given to `forward_trace_with` are applied too.
 #[cfg(feature = "trace")]
 pub fn backward_trace<T: Tracable, U, V>(
     input: IResult<T, U, V>,
@@ -659,130 +1468,94 @@ pub fn backward_trace<T: Tracable, U, V>(
 ) -> IResult<T, U, V> {
     let depth = info.depth;
 
-    crate::TRACABLE_STORAGE.with(|storage| {
+    let frame = crate::TRACABLE_STORAGE.with(|storage| {
         let cnt = *storage.borrow_mut().get_cumulative(name, depth).unwrap();
         storage.borrow_mut().inc_cumulative_histogram(name, cnt);
+        storage.borrow_mut().inc_result(name, input.is_ok());
+        let frame = storage.borrow_mut().pop_frame().unwrap();
+        if input.is_ok() {
+            storage.borrow_mut().edge_mut(frame.name).1 += 1;
+        }
+        frame
     });
 
-    if info.backward {
+    if let (Err(_), Some(offset)) = (&input, frame.offset) {
+        if !frame.options.silent {
+            crate::TRACABLE_STORAGE
+                .with(|storage| storage.borrow_mut().update_failure(&frame, offset));
+        }
+    }
+
+    if info.backward && frame.shown {
         let backward_count = crate::TRACABLE_STORAGE.with(|storage| {
             storage.borrow_mut().inc_backward_count();
             storage.borrow().get_backward_count()
         });
 
-        let forward_backword = if info.forward {
-            format!(
-                "{:<count_width$} {:<count_width$}",
-                "",
-                backward_count,
-                count_width = info.count_width
-            )
-        } else {
-            format!(
-                "{:<count_width$}",
-                backward_count,
-                count_width = info.count_width
-            )
+        let timestamp = crate::TRACABLE_STORAGE.with(|storage| storage.borrow_mut().elapsed());
+        let event = match &input {
+            Ok((s, _)) => TraceEvent::ExitOk {
+                name: String::from(name),
+                depth,
+                count: backward_count,
+                offset: frame.offset,
+                location: frame.location,
+                consumed: s
+                    .offset()
+                    .zip(frame.offset)
+                    .map(|(x, y)| x.saturating_sub(y)),
+                folded: frame.folded,
+                fragment: s.display_fragment(),
+                input: s.format(),
+                timestamp,
+            },
+            Err(x) => TraceEvent::ExitErr {
+                name: String::from(name),
+                depth,
+                count: backward_count,
+                offset: frame.offset,
+                location: frame.location,
+                folded: frame.folded,
+                kind: TraceErrorKind::from_err(x),
+                snippet: frame.snippet.clone(),
+                timestamp,
+            },
         };
+        emit_event(&info, &event);
+    }
 
-        let color_ok = if info.color { "\u{001b}[1;32m" } else { "" };
-        let color_err = if info.color { "\u{001b}[1;31m" } else { "" };
-        let reset = if info.color { "\u{001b}[0m" } else { "" };
-        let folded = if info.folded(name) { "+" } else { " " };
-
-        let mut lock = if cfg!(feature = "stderr") {
-            Box::new(std::io::stderr().lock()) as Box<dyn Write>
-        } else {
-            Box::new(std::io::stdout().lock()) as Box<dyn Write>
-        };
+    // Restore the depth and the trace setting changed by folding
+    input.map(|(s, x)| (s.set_tracable_info(info), x))
+}
 
-        match input {
-            Ok((s, x)) => {
-                writeln!(
-                    lock,
-                    "{} : {:<parser_width$} : {}",
-                    forward_backword,
-                    format!(
-                        "{}{}<- {} {}{}",
-                        color_ok,
-                        " ".repeat(depth),
-                        name,
-                        folded,
-                        reset
-                    ),
-                    s.format(),
-                    parser_width = info.parser_width,
-                )
-                .unwrap();
-
-                let s = if info.folded(name) {
-                    let info = s
-                        .get_tracable_info()
-                        .forward(info.forward)
-                        .backward(info.backward)
-                        .custom(info.custom);
-                    s.set_tracable_info(info)
-                } else {
-                    s
-                };
-
-                Ok((s.dec_depth(), x))
-            }
-            Err(x) => {
-                writeln!(
-                    lock,
-                    "{} : {:<parser_width$}",
-                    forward_backword,
-                    format!(
-                        "{}{}<- {} {}{}",
-                        color_err,
-                        " ".repeat(depth),
-                        name,
-                        folded,
-                        reset
-                    ),
-                    parser_width = info.parser_width,
-                )
-                .unwrap();
-                Err(x)
-            }
-        }
-    } else {
-        input
-    }
+#[cfg(feature = "trace")]
+fn emit_event(info: &TracableInfo, event: &TraceEvent) {
+    crate::TRACABLE_STORAGE.with(|storage| storage.borrow_mut().lines += 1);
+    let start = Instant::now();
+    crate::sink::with_sink(|sink| sink.event(info, event));
+    crate::TRACABLE_STORAGE.with(|storage| storage.borrow_mut().sink_time += start.elapsed());
 }
 
 /// Function to display custom trace.
+///
+/// `color` is the escape sequence of the message (e.g. `"\u{001b}[1;34m"`).
+/// If it is empty, `TableSink` uses the `custom` style of `Theme`.
 #[cfg(feature = "trace")]
 pub fn custom_trace<T: Tracable>(input: &T, name: &str, message: &str, color: &str) {
     let info = input.get_tracable_info();
 
-    if info.custom {
-        let depth = info.depth;
-        let forward_backword = format!(
-            "{:<count_width$} {:<count_width$}",
-            "",
-            "",
-            count_width = info.count_width
-        );
-
-        let color = if info.color { color } else { "" };
-        let reset = if info.color { "\u{001b}[0m" } else { "" };
-
-        let mut lock = if cfg!(feature = "stderr") {
-            Box::new(std::io::stderr().lock()) as Box<dyn Write>
-        } else {
-            Box::new(std::io::stdout().lock()) as Box<dyn Write>
+    if info.custom && info.visible(input.offset()) {
+        let timestamp = crate::TRACABLE_STORAGE.with(|storage| storage.borrow_mut().elapsed());
+        let event = TraceEvent::Custom {
+            name: String::from(name),
+            depth: info.depth,
+            offset: input.offset(),
+            location: input.location(),
+            message: String::from(message),
+            color: String::from(color),
+            snippet: if info.snippet { input.snippet() } else { None },
+            timestamp,
         };
-
-        writeln!(
-            lock,
-            "{} : {:<parser_width$} : {}",
-            forward_backword,
-            format!("{}{}   {}{}", color, " ".repeat(depth), name, reset),
-            message,
-            parser_width = info.parser_width,
-        )
-        .unwrap();
+        emit_event(&info, &event);
     }
 }
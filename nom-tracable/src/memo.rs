@@ -0,0 +1,137 @@
+use crate::Tracable;
+use nom::{IResult, Slice};
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::RangeFrom;
+
+/// Memoized successful result of a parser.
+struct MemoEntry {
+    consumed: usize,
+    output: Box<dyn Any>,
+}
+
+/// Memo shared by the parsers with `#[tracable_parser(memo)]`.
+///
+/// The entries are kept while the outermost parser with `memo` argument runs,
+/// because the same offset of another input must not hit them.
+#[derive(Default)]
+struct MemoTable {
+    depth: usize,
+    entries: HashMap<(&'static str, usize), MemoEntry>,
+}
+
+thread_local!(
+    static MEMO_TABLE: RefCell<MemoTable> = RefCell::new(MemoTable::default())
+);
+
+/// Scope of a parser with `memo` argument which clears the memo at the exit of the outermost one.
+///
+/// This is dropped even if the parser panics.
+struct MemoScope;
+
+impl MemoScope {
+    fn enter() -> Self {
+        MEMO_TABLE.with(|table| table.borrow_mut().depth += 1);
+        MemoScope
+    }
+}
+
+impl Drop for MemoScope {
+    fn drop(&mut self) {
+        MEMO_TABLE.with(|table| {
+            let mut table = table.borrow_mut();
+            table.depth -= 1;
+            if table.depth == 0 {
+                table.entries.clear();
+            }
+        });
+    }
+}
+
+/// Function to run the parser body with memoization.
+/// This is inserted by `#[tracable_parser(memo)]` regardless of trace.
+///
+/// `key` is the unique path of the parser function.
+/// Only successful results are memoized,
+/// so failures are re-parsed to return the original error.
+/// The input without offset is always parsed because the memo can't be looked up.
+pub fn memo<T, U, V, F>(input: T, key: &'static str, body: F) -> IResult<T, U, V>
+where
+    T: Tracable + Slice<RangeFrom<usize>>,
+    U: Clone + 'static,
+    F: FnOnce(T) -> IResult<T, U, V>,
+{
+    memo_with(input, key, body, |_| ())
+}
+
+/// Function to run the parser body with memoization and count memo hits.
+/// This is inserted by `#[tracable_parser(memo)]` while trace is enabled.
+///
+/// `name` is the parser name shown in trace.
+#[cfg(feature = "trace")]
+pub fn memo_trace<T, U, V, F>(input: T, key: &'static str, name: &str, body: F) -> IResult<T, U, V>
+where
+    T: Tracable + Slice<RangeFrom<usize>>,
+    U: Clone + 'static,
+    F: FnOnce(T) -> IResult<T, U, V>,
+{
+    let body = |input| {
+        crate::TRACABLE_STORAGE.with(|storage| {
+            *storage
+                .borrow_mut()
+                .memo_misses
+                .entry(String::from(name))
+                .or_insert(0) += 1;
+        });
+        body(input)
+    };
+    let hit = |input: &T| {
+        crate::TRACABLE_STORAGE.with(|storage| {
+            *storage
+                .borrow_mut()
+                .memo_hits
+                .entry(String::from(name))
+                .or_insert(0) += 1;
+        });
+        crate::custom_trace(input, name, "memo hit", "");
+    };
+    memo_with(input, key, body, hit)
+}
+
+fn memo_with<T, U, V, F, G>(input: T, key: &'static str, body: F, hit: G) -> IResult<T, U, V>
+where
+    T: Tracable + Slice<RangeFrom<usize>>,
+    U: Clone + 'static,
+    F: FnOnce(T) -> IResult<T, U, V>,
+    G: FnOnce(&T),
+{
+    let offset = match input.offset() {
+        Some(x) => x,
+        None => return body(input),
+    };
+
+    let memoized = MEMO_TABLE.with(|table| {
+        let table = table.borrow();
+        let entry = table.entries.get(&(key, offset))?;
+        let output = entry.output.downcast_ref::<U>()?.clone();
+        Some((entry.consumed, output))
+    });
+    if let Some((consumed, output)) = memoized {
+        hit(&input);
+        return Ok((input.slice(consumed..), output));
+    }
+
+    let _scope = MemoScope::enter();
+    let ret = body(input);
+    if let Ok((s, x)) = &ret {
+        if let Some(end) = s.offset() {
+            let entry = MemoEntry {
+                consumed: end.saturating_sub(offset),
+                output: Box::new(x.clone()),
+            };
+            MEMO_TABLE.with(|table| table.borrow_mut().entries.insert((key, offset), entry));
+        }
+    }
+    ret
+}
@@ -0,0 +1,108 @@
+use crate::{with_trace_sink, TracableInfo, TraceEvent, TraceSink};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Sink to record trace events into memory.
+///
+/// This is useful to assert the call sequence of parsers in unit tests.
+/// If `trace` feature is disabled, no event is recorded.
+///
+/// ```
+/// # use nom::character::complete::*;
+/// # use nom::IResult;
+/// # use nom_locate::LocatedSpan;
+/// # use nom_tracable::{tracable_parser, TraceRecorder, TracableInfo};
+/// #
+/// # type Span<'a> = LocatedSpan<&'a str, TracableInfo>;
+/// #
+/// # #[tracable_parser]
+/// # pub fn term(s: Span) -> IResult<Span, String> {
+/// #     let (s, x) = char('1')(s)?;
+/// #     Ok((s, x.to_string()))
+/// # }
+/// #
+/// let (ret, trace) = TraceRecorder::record(|| term(LocatedSpan::new_extra("2", TracableInfo::new())));
+/// assert!(ret.is_err());
+/// if nom_tracable::trace_enabled() {
+///     assert!(trace.failed_at("term", 0));
+/// }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TraceRecorder {
+    events: Vec<TraceEvent>,
+}
+
+impl TraceRecorder {
+    pub fn new() -> Self {
+        TraceRecorder::default()
+    }
+
+    /// Call `f` with a new recorder installed as sink, and return the recorder.
+    pub fn record<F: FnOnce() -> R, R>(f: F) -> (R, TraceRecorder) {
+        let recorder = Rc::new(RefCell::new(TraceRecorder::new()));
+        let ret = with_trace_sink(recorder.clone(), f);
+        let recorder = recorder.borrow().clone();
+        (ret, recorder)
+    }
+
+    /// All recorded events.
+    pub fn events(&self) -> &[TraceEvent] {
+        &self.events
+    }
+
+    /// Clear recorded events.
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    /// `Enter` events of the parser.
+    pub fn calls<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a TraceEvent> + 'a {
+        self.events
+            .iter()
+            .filter(move |x| matches!(x, TraceEvent::Enter { .. }) && x.name() == name)
+    }
+
+    /// `ExitErr` events of the parser.
+    pub fn failures<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a TraceEvent> + 'a {
+        self.events
+            .iter()
+            .filter(move |x| matches!(x, TraceEvent::ExitErr { .. }) && x.name() == name)
+    }
+
+    /// Names of parsers which were entered at `offset` and failed.
+    pub fn failures_at(&self, offset: usize) -> Vec<&str> {
+        self.events
+            .iter()
+            .filter(|x| matches!(x, TraceEvent::ExitErr { .. }) && x.offset() == Some(offset))
+            .map(|x| x.name())
+            .collect()
+    }
+
+    /// Whether the parser was entered at `offset`.
+    pub fn entered_at(&self, name: &str, offset: usize) -> bool {
+        self.calls(name).any(|x| x.offset() == Some(offset))
+    }
+
+    /// Whether the parser was entered at `offset` and failed.
+    pub fn failed_at(&self, name: &str, offset: usize) -> bool {
+        self.failures(name).any(|x| x.offset() == Some(offset))
+    }
+
+    /// Whether the parser was entered at `offset` and succeeded.
+    pub fn succeeded_at(&self, name: &str, offset: usize) -> bool {
+        self.events.iter().any(|x| {
+            matches!(x, TraceEvent::ExitOk { .. }) && x.name() == name && x.offset() == Some(offset)
+        })
+    }
+
+    /// Max depth reached in recorded events.
+    pub fn max_depth(&self) -> usize {
+        self.events.iter().map(|x| x.depth()).max().unwrap_or(0)
+    }
+}
+
+impl TraceSink for TraceRecorder {
+    fn event(&mut self, _info: &TracableInfo, event: &TraceEvent) {
+        self.events.push(event.clone());
+    }
+}
@@ -0,0 +1,20 @@
+use std::collections::BTreeMap;
+
+/// Repetition of a parser invoked at the same offset.
+///
+/// This is generated by `reparse`.
+/// The parsers with many redundant calls are candidates of memoization.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Reparse {
+    /// Parser name
+    pub name: String,
+    /// Call count exceeding one call per offset
+    pub redundant_calls: usize,
+    /// Number of offsets at which the parser was invoked repeatedly
+    pub offsets: usize,
+    /// Max call count at an offset
+    pub max_calls: usize,
+    /// Callers of the repeated calls and their call counts
+    pub callers: BTreeMap<String, usize>,
+}
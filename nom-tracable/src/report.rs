@@ -0,0 +1,49 @@
+use std::fmt;
+
+/// Report of the furthest failure in a parse.
+///
+/// This is generated by `furthest_failure`.
+/// `offset` and `location` are the input position at which the failed parsers were entered,
+/// and `expected` is the names of the parsers failed at the position in the order of failure.
+/// A parser failed by the failure of its only failed child at the position is shown instead of the child,
+/// and a parser failed after the failures of several alternatives at the position is omitted.
+///
+/// The report is displayed like below:
+///
+/// ```text
+/// at line 3, col 14: expected one of expr_plus, expr_minus, term
+/// 3 | a = 1 + (2 * ;
+///   |              ^
+/// ```
+///
+/// The snippet is shown only if `TracableInfo::snippet` is enabled.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FailureReport {
+    /// Offset of the furthest failure
+    pub offset: usize,
+    /// Line and column of the furthest failure
+    pub location: Option<(u32, usize)>,
+    /// Parsers failed at the furthest offset
+    pub expected: Vec<String>,
+    /// Source snippet at the furthest failure if `TracableInfo::snippet` is enabled
+    pub snippet: Option<String>,
+}
+
+impl fmt::Display for FailureReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.location {
+            Some((line, column)) => write!(f, "at line {}, col {}: ", line, column)?,
+            None => write!(f, "at offset {}: ", self.offset)?,
+        }
+        if self.expected.len() == 1 {
+            write!(f, "expected {}", self.expected[0])?;
+        } else {
+            write!(f, "expected one of {}", self.expected.join(", "))?;
+        }
+        if let Some(x) = &self.snippet {
+            write!(f, "\n{}", x)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,571 @@
+#[cfg(feature = "trace")]
+use crate::Style;
+use crate::{Theme, TracableInfo, TraceEvent};
+use std::cell::RefCell;
+use std::io::{IsTerminal, Write};
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Trait to receive trace output.
+///
+/// The sink is installed per thread by `set_trace_sink` or `with_trace_sink`.
+/// The default sink is `TableSink` which displays the colored table to stdout
+/// (or stderr if `stderr` feature is enabled).
+pub trait TraceSink {
+    /// Called once when the top-level parser is started.
+    ///
+    /// `header` is the column header of the input type given by `Tracable::header`.
+    fn header(&mut self, _info: &TracableInfo, _header: &str) {}
+
+    /// Called at each trace event.
+    fn event(&mut self, info: &TracableInfo, event: &TraceEvent);
+
+    /// Called by `histogram` and `cumulative_histogram`.
+    ///
+    /// `histogram` is sorted by count in descending order.
+    fn histogram(&mut self, _title: &str, _histogram: &[(&str, usize)]) {}
+
+    /// Called by `timing_histogram`.
+    ///
+    /// `histogram` has the parser name, the total time and the self time,
+    /// and is sorted by the self time in descending order.
+    fn timing_histogram(&mut self, _histogram: &[(&str, Duration, Duration)]) {}
+}
+
+impl<S: TraceSink + ?Sized> TraceSink for Box<S> {
+    fn header(&mut self, info: &TracableInfo, header: &str) {
+        (**self).header(info, header)
+    }
+
+    fn event(&mut self, info: &TracableInfo, event: &TraceEvent) {
+        (**self).event(info, event)
+    }
+
+    fn histogram(&mut self, title: &str, histogram: &[(&str, usize)]) {
+        (**self).histogram(title, histogram)
+    }
+
+    fn timing_histogram(&mut self, histogram: &[(&str, Duration, Duration)]) {
+        (**self).timing_histogram(histogram)
+    }
+}
+
+/// `Rc<RefCell<S>>` can be used as sink to refer the sink after parsing.
+impl<S: TraceSink + ?Sized> TraceSink for Rc<RefCell<S>> {
+    fn header(&mut self, info: &TracableInfo, header: &str) {
+        self.borrow_mut().header(info, header)
+    }
+
+    fn event(&mut self, info: &TracableInfo, event: &TraceEvent) {
+        self.borrow_mut().event(info, event)
+    }
+
+    fn histogram(&mut self, title: &str, histogram: &[(&str, usize)]) {
+        self.borrow_mut().histogram(title, histogram)
+    }
+
+    fn timing_histogram(&mut self, histogram: &[(&str, Duration, Duration)]) {
+        self.borrow_mut().timing_histogram(histogram)
+    }
+}
+
+/// Color setting of trace output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Always colored
+    Always,
+    /// Never colored
+    Never,
+    /// Colored if the output is a terminal.
+    ///
+    /// `NO_COLOR` disables color and `CLICOLOR_FORCE` enables color regardless of the output.
+    /// They are read when `TableSink` is created.
+    #[default]
+    Auto,
+}
+
+impl From<bool> for ColorMode {
+    fn from(x: bool) -> Self {
+        if x {
+            ColorMode::Always
+        } else {
+            ColorMode::Never
+        }
+    }
+}
+
+/// Sink to display trace by colored table format.
+pub struct TableSink {
+    #[allow(dead_code)]
+    writer: Box<dyn Write>,
+    #[allow(dead_code)]
+    auto_color: bool,
+    #[allow(dead_code)]
+    theme: Theme,
+}
+
+impl TableSink {
+    /// Create a sink writing to `writer`.
+    ///
+    /// `writer` is not regarded as a terminal, so `ColorMode::Auto` is not colored.
+    pub fn new<W: Write + 'static>(writer: W) -> Self {
+        TableSink {
+            writer: Box::new(writer),
+            auto_color: auto_color(false),
+            theme: Theme::default(),
+        }
+    }
+
+    /// Set the color theme.
+    pub fn theme(mut self, x: Theme) -> Self {
+        self.theme = x;
+        self
+    }
+
+    /// Create a sink writing to stdout.
+    pub fn stdout() -> Self {
+        let stdout = std::io::stdout();
+        TableSink {
+            auto_color: auto_color(stdout.is_terminal()),
+            writer: Box::new(stdout),
+            theme: Theme::default(),
+        }
+    }
+
+    /// Create a sink writing to stderr.
+    pub fn stderr() -> Self {
+        let stderr = std::io::stderr();
+        TableSink {
+            auto_color: auto_color(stderr.is_terminal()),
+            writer: Box::new(stderr),
+            theme: Theme::default(),
+        }
+    }
+}
+
+/// Whether `ColorMode::Auto` is colored.
+///
+/// This is decided when the sink is created to avoid reading environment variables at each event.
+fn auto_color(terminal: bool) -> bool {
+    let env = |key| std::env::var_os(key).filter(|x| !x.is_empty());
+    if env("NO_COLOR").is_some() {
+        false
+    } else if env("CLICOLOR_FORCE").is_some_and(|x| x != "0") {
+        true
+    } else {
+        terminal
+    }
+}
+
+impl Default for TableSink {
+    fn default() -> Self {
+        if cfg!(feature = "stderr") {
+            TableSink::stderr()
+        } else {
+            TableSink::stdout()
+        }
+    }
+}
+
+#[cfg(feature = "trace")]
+impl TableSink {
+    fn count_column(info: &TracableInfo, forward: &str, backward: &str) -> String {
+        if info.forward & info.backward {
+            format!(
+                "{:<count_width$} {:<count_width$}",
+                forward,
+                backward,
+                count_width = info.count_width
+            )
+        } else if info.forward {
+            format!("{:<count_width$}", forward, count_width = info.count_width)
+        } else {
+            format!("{:<count_width$}", backward, count_width = info.count_width)
+        }
+    }
+
+    fn use_color(&self, info: &TracableInfo) -> bool {
+        match info.color {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => self.auto_color,
+        }
+    }
+
+    /// Render the parser column padded to the parser width excluding escape sequences.
+    fn parser_column(
+        &self,
+        info: &TracableInfo,
+        depth: usize,
+        label: &str,
+        prefix: &str,
+        folded: bool,
+    ) -> String {
+        let use_color = self.use_color(info);
+        let paint = |style: &Style, text: &str| {
+            if use_color {
+                style.paint(text)
+            } else {
+                String::from(text)
+            }
+        };
+
+        let palette = &self.theme.depth;
+        let indent: String = if palette.is_empty() {
+            " ".repeat(depth)
+        } else {
+            (0..depth)
+                .map(|x| paint(&palette[x % palette.len()], " "))
+                .collect()
+        };
+        let folded = if folded {
+            paint(&self.theme.folded, "+")
+        } else {
+            String::from(" ")
+        };
+        let width = depth + label.chars().count() + 2;
+        let padding = " ".repeat(info.parser_width.saturating_sub(width));
+
+        let label = if use_color && !prefix.is_empty() {
+            format!("{}{}\u{001b}[0m", prefix, label)
+        } else {
+            String::from(label)
+        };
+
+        format!("{}{} {}{}", indent, label, folded, padding)
+    }
+
+    fn write_snippet(&mut self, info: &TracableInfo, depth: usize, snippet: &Option<String>) {
+        if let Some(snippet) = snippet {
+            let forward_backword = TableSink::count_column(info, "", "");
+            for line in snippet.lines() {
+                writeln!(
+                    self.writer,
+                    "{} : {}{}",
+                    forward_backword,
+                    " ".repeat(depth + 3),
+                    line
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    /// Render the input column split at the first ` : ` into the offset and the fragment.
+    fn input_column(&self, info: &TracableInfo, input: &str) -> String {
+        if !self.use_color(info) {
+            return String::from(input);
+        }
+
+        match input.find(" : ") {
+            Some(x) => format!(
+                "{} : {}",
+                self.theme.offset.paint(&input[..x]),
+                self.theme.fragment.paint(&input[x + 3..])
+            ),
+            None => self.theme.fragment.paint(input),
+        }
+    }
+}
+
+#[cfg(feature = "trace")]
+impl TraceSink for TableSink {
+    fn header(&mut self, info: &TracableInfo, header: &str) {
+        let forward_backword = TableSink::count_column(info, "forward", "backward");
+
+        writeln!(
+            self.writer,
+            "\n{} : {:<parser_width$} : {}",
+            forward_backword,
+            "parser",
+            header,
+            parser_width = info.parser_width,
+        )
+        .unwrap();
+    }
+
+    fn event(&mut self, info: &TracableInfo, event: &TraceEvent) {
+        match event {
+            TraceEvent::Enter {
+                name,
+                depth,
+                count,
+                folded,
+                input,
+                ..
+            } => {
+                let forward_backword = TableSink::count_column(info, &count.to_string(), "");
+                let label = format!("-> {}", name);
+                let prefix = self.theme.enter.prefix();
+
+                writeln!(
+                    self.writer,
+                    "{} : {} : {}",
+                    forward_backword,
+                    self.parser_column(info, *depth, &label, &prefix, *folded),
+                    self.input_column(info, input),
+                )
+                .unwrap();
+            }
+            TraceEvent::ExitOk {
+                name,
+                depth,
+                count,
+                folded,
+                input,
+                ..
+            } => {
+                let forward_backword = TableSink::count_column(info, "", &count.to_string());
+                let label = format!("<- {}", name);
+                let prefix = self.theme.success.prefix();
+
+                writeln!(
+                    self.writer,
+                    "{} : {} : {}",
+                    forward_backword,
+                    self.parser_column(info, *depth, &label, &prefix, *folded),
+                    self.input_column(info, input),
+                )
+                .unwrap();
+            }
+            TraceEvent::ExitErr {
+                name,
+                depth,
+                count,
+                folded,
+                snippet,
+                ..
+            } => {
+                let forward_backword = TableSink::count_column(info, "", &count.to_string());
+                let label = format!("<- {}", name);
+                let prefix = self.theme.failure.prefix();
+
+                writeln!(
+                    self.writer,
+                    "{} : {}",
+                    forward_backword,
+                    self.parser_column(info, *depth, &label, &prefix, *folded),
+                )
+                .unwrap();
+                self.write_snippet(info, *depth, snippet);
+            }
+            TraceEvent::Custom {
+                name,
+                depth,
+                message,
+                color,
+                snippet,
+                ..
+            } => {
+                let forward_backword = format!(
+                    "{:<count_width$} {:<count_width$}",
+                    "",
+                    "",
+                    count_width = info.count_width
+                );
+                let label = format!("   {}", name);
+                let prefix = if color.is_empty() {
+                    self.theme.custom.prefix()
+                } else {
+                    color.clone()
+                };
+
+                writeln!(
+                    self.writer,
+                    "{} : {} : {}",
+                    forward_backword,
+                    self.parser_column(info, *depth, &label, &prefix, false),
+                    message,
+                )
+                .unwrap();
+                self.write_snippet(info, *depth, snippet);
+            }
+        }
+    }
+
+    fn histogram(&mut self, title: &str, histogram: &[(&str, usize)]) {
+        let mut max_parser_len = "parser".len();
+        let mut max_count = 0;
+        let mut max_count_len = "count".len();
+        for (p, c) in histogram {
+            max_parser_len = max_parser_len.max(p.len());
+            max_count = max_count.max(*c);
+            max_count_len = max_count_len.max(format!("{}", c).len());
+        }
+
+        let bar_length = 50;
+
+        writeln!(
+            self.writer,
+            "\n{:<parser$} | {:<bar$} | count",
+            "parser",
+            title,
+            parser = max_parser_len,
+            bar = bar_length,
+        )
+        .unwrap();
+
+        writeln!(
+            self.writer,
+            "{:<parser$} | {:<bar$} | {}",
+            "-".repeat(max_parser_len),
+            "-".repeat(bar_length),
+            "-".repeat(max_count_len),
+            parser = max_parser_len,
+            bar = bar_length,
+        )
+        .unwrap();
+
+        for (p, c) in histogram {
+            let bar = *c * bar_length / max_count;
+            if bar > 0 {
+                writeln!(
+                    self.writer,
+                    "{:<parser$} | {}{} | {}",
+                    p,
+                    ".".repeat(bar),
+                    " ".repeat(bar_length - bar),
+                    c,
+                    parser = max_parser_len,
+                )
+                .unwrap();
+            }
+        }
+        writeln!(self.writer).unwrap();
+        self.writer.flush().unwrap();
+    }
+
+    fn timing_histogram(&mut self, histogram: &[(&str, Duration, Duration)]) {
+        let mut max_parser_len = "parser".len();
+        let mut max_self = Duration::default();
+        let mut max_total_len = "total".len();
+        let mut max_self_len = "self".len();
+        for (p, t, s) in histogram {
+            max_parser_len = max_parser_len.max(p.len());
+            max_self = max_self.max(*s);
+            max_total_len = max_total_len.max(format!("{:.3?}", t).len());
+            max_self_len = max_self_len.max(format!("{:.3?}", s).len());
+        }
+
+        let bar_length = 50;
+
+        writeln!(
+            self.writer,
+            "\n{:<parser$} | {:<bar$} | {:<total$} | self",
+            "parser",
+            "timing histogram",
+            "total",
+            parser = max_parser_len,
+            bar = bar_length,
+            total = max_total_len,
+        )
+        .unwrap();
+
+        writeln!(
+            self.writer,
+            "{:<parser$} | {:<bar$} | {} | {}",
+            "-".repeat(max_parser_len),
+            "-".repeat(bar_length),
+            "-".repeat(max_total_len),
+            "-".repeat(max_self_len),
+            parser = max_parser_len,
+            bar = bar_length,
+        )
+        .unwrap();
+
+        for (p, t, s) in histogram {
+            let bar = if max_self.is_zero() {
+                0
+            } else {
+                (s.as_nanos() * bar_length as u128 / max_self.as_nanos()) as usize
+            };
+            writeln!(
+                self.writer,
+                "{:<parser$} | {}{} | {:<total$} | {:.3?}",
+                p,
+                ".".repeat(bar),
+                " ".repeat(bar_length - bar),
+                format!("{:.3?}", t),
+                s,
+                parser = max_parser_len,
+                total = max_total_len,
+            )
+            .unwrap();
+        }
+        writeln!(self.writer).unwrap();
+        self.writer.flush().unwrap();
+    }
+}
+
+#[cfg(not(feature = "trace"))]
+impl TraceSink for TableSink {
+    fn event(&mut self, _info: &TracableInfo, _event: &TraceEvent) {}
+}
+
+#[cfg(feature = "trace")]
+thread_local!(
+    static TRACE_SINK: RefCell<Box<dyn TraceSink>> = RefCell::new(Box::new(TableSink::default()))
+);
+
+/// Install the sink receiving trace output of the current thread.
+///
+/// The previously installed sink is returned.
+/// If `trace` feature is disabled, nothing is installed and `sink` is returned as is.
+pub fn set_trace_sink<S: TraceSink + 'static>(sink: S) -> Box<dyn TraceSink> {
+    set_trace_sink_internal(Box::new(sink))
+}
+
+#[cfg(feature = "trace")]
+fn set_trace_sink_internal(sink: Box<dyn TraceSink>) -> Box<dyn TraceSink> {
+    TRACE_SINK.with(|x| std::mem::replace(&mut *x.borrow_mut(), sink))
+}
+
+#[cfg(not(feature = "trace"))]
+fn set_trace_sink_internal(sink: Box<dyn TraceSink>) -> Box<dyn TraceSink> {
+    sink
+}
+
+/// Call `f` with `sink` installed, and restore the previous sink after that.
+///
+/// The previous sink is restored even if `f` panics.
+///
+/// ```
+/// # use nom::character::complete::*;
+/// # use nom::IResult;
+/// # use nom_locate::LocatedSpan;
+/// # use nom_tracable::{tracable_parser, with_trace_sink, TableSink, TracableInfo};
+/// #
+/// # type Span<'a> = LocatedSpan<&'a str, TracableInfo>;
+/// #
+/// # #[tracable_parser]
+/// # pub fn term(s: Span) -> IResult<Span, String> {
+/// #     let (s, x) = char('1')(s)?;
+/// #     Ok((s, x.to_string()))
+/// # }
+/// #
+/// // Discard trace output of this parse
+/// let ret = with_trace_sink(TableSink::new(std::io::sink()), || {
+///     term(LocatedSpan::new_extra("1", TracableInfo::new()))
+/// });
+/// ```
+pub fn with_trace_sink<S: TraceSink + 'static, F: FnOnce() -> R, R>(sink: S, f: F) -> R {
+    let _restore = RestoreSink(Some(set_trace_sink_internal(Box::new(sink))));
+    f()
+}
+
+/// Guard to restore the previous sink when `with_trace_sink` returns or unwinds.
+struct RestoreSink(Option<Box<dyn TraceSink>>);
+
+impl Drop for RestoreSink {
+    fn drop(&mut self) {
+        if let Some(prev) = self.0.take() {
+            set_trace_sink_internal(prev);
+        }
+    }
+}
+
+/// Call `f` with the sink of the current thread.
+#[cfg(feature = "trace")]
+pub(crate) fn with_sink<F: FnOnce(&mut dyn TraceSink)>(f: F) {
+    TRACE_SINK.with(|x| f(x.borrow_mut().as_mut()));
+}
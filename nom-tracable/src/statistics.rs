@@ -0,0 +1,48 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Statistics of a parse.
+///
+/// This is generated by `statistics`.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParseStatistics {
+    /// Statistics of each parser
+    pub parsers: BTreeMap<String, ParserStatistics>,
+    /// Max depth reached in the parse
+    pub max_depth: usize,
+}
+
+/// Statistics of a parser.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParserStatistics {
+    /// Call count
+    pub calls: usize,
+    /// Call count including the counts of children parsers
+    pub cumulative_calls: usize,
+    /// Success count
+    pub success: usize,
+    /// Failure count
+    pub failure: usize,
+    /// Elapsed time including the time of children parsers
+    pub total_time: Duration,
+    /// Elapsed time excluding the time of children parsers
+    pub self_time: Duration,
+    /// Memo hit count of `#[tracable_parser(memo)]`
+    pub memo_hits: usize,
+    /// Memo miss count of `#[tracable_parser(memo)]`
+    pub memo_misses: usize,
+}
+
+impl ParseStatistics {
+    /// Total call count of all parsers.
+    pub fn total_calls(&self) -> usize {
+        self.parsers.values().map(|x| x.calls).sum()
+    }
+
+    /// Statistics of the parser.
+    pub fn parser(&self, name: &str) -> Option<&ParserStatistics> {
+        self.parsers.get(name)
+    }
+}
@@ -0,0 +1,197 @@
+/// Color of terminal text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    /// 256-color palette
+    Fixed(u8),
+    /// Truecolor
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    fn code(&self, base: u8) -> String {
+        match self {
+            Color::Black => format!("{}", base),
+            Color::Red => format!("{}", base + 1),
+            Color::Green => format!("{}", base + 2),
+            Color::Yellow => format!("{}", base + 3),
+            Color::Blue => format!("{}", base + 4),
+            Color::Magenta => format!("{}", base + 5),
+            Color::Cyan => format!("{}", base + 6),
+            Color::White => format!("{}", base + 7),
+            Color::Fixed(x) => format!("{};5;{}", base + 8, x),
+            Color::Rgb(r, g, b) => format!("{};2;{};{};{}", base + 8, r, g, b),
+        }
+    }
+}
+
+/// Style of terminal text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub bold: bool,
+}
+
+impl Style {
+    pub fn new() -> Self {
+        Style::default()
+    }
+
+    /// Set the foreground color.
+    pub fn fg(mut self, x: Color) -> Self {
+        self.fg = Some(x);
+        self
+    }
+
+    /// Set the background color.
+    pub fn bg(mut self, x: Color) -> Self {
+        self.bg = Some(x);
+        self
+    }
+
+    /// Set bold.
+    pub fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    /// Whether the style has no effect.
+    pub fn is_plain(&self) -> bool {
+        self.fg.is_none() && self.bg.is_none() && !self.bold
+    }
+
+    /// Escape sequence to start the style.
+    ///
+    /// Returns an empty string if the style is plain.
+    pub fn prefix(&self) -> String {
+        let mut codes = Vec::new();
+        if self.bold {
+            codes.push(String::from("1"));
+        }
+        if let Some(x) = self.fg {
+            codes.push(x.code(30));
+        }
+        if let Some(x) = self.bg {
+            codes.push(x.code(40));
+        }
+
+        if codes.is_empty() {
+            String::new()
+        } else {
+            format!("\u{001b}[{}m", codes.join(";"))
+        }
+    }
+
+    /// Wrap `text` by the escape sequences of the style.
+    pub fn paint(&self, text: &str) -> String {
+        if self.is_plain() || text.is_empty() {
+            String::from(text)
+        } else {
+            format!("{}{}\u{001b}[0m", self.prefix(), text)
+        }
+    }
+}
+
+/// Color theme of `TableSink`.
+///
+/// Each field is the style of a role in the trace table.
+/// `depth` is the palette of the indentation background,
+/// and the indentation is not colored if it is empty.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Theme {
+    /// Entered parser
+    pub enter: Style,
+    /// Succeeded parser
+    pub success: Style,
+    /// Failed parser
+    pub failure: Style,
+    /// Custom trace without explicit color
+    pub custom: Style,
+    /// Folded marker `+`
+    pub folded: Style,
+    /// Input offset
+    pub offset: Style,
+    /// Input fragment
+    pub fragment: Style,
+    /// Indentation colors by depth
+    pub depth: Vec<Style>,
+}
+
+impl Theme {
+    /// Theme for dark background (default).
+    pub fn dark() -> Self {
+        Theme {
+            enter: Style::new().fg(Color::White).bold(),
+            success: Style::new().fg(Color::Green).bold(),
+            failure: Style::new().fg(Color::Red).bold(),
+            custom: Style::new().fg(Color::Cyan).bold(),
+            folded: Style::new().fg(Color::Yellow).bold(),
+            offset: Style::new(),
+            fragment: Style::new(),
+            depth: Vec::new(),
+        }
+    }
+
+    /// Theme for light background.
+    pub fn light() -> Self {
+        Theme {
+            enter: Style::new().fg(Color::Black).bold(),
+            success: Style::new().fg(Color::Fixed(28)).bold(),
+            failure: Style::new().fg(Color::Fixed(160)).bold(),
+            custom: Style::new().fg(Color::Blue).bold(),
+            folded: Style::new().fg(Color::Magenta).bold(),
+            offset: Style::new().fg(Color::Fixed(242)),
+            fragment: Style::new(),
+            depth: Vec::new(),
+        }
+    }
+
+    /// Theme with high contrast colors.
+    pub fn high_contrast() -> Self {
+        Theme {
+            enter: Style::new().fg(Color::Black).bg(Color::White).bold(),
+            success: Style::new().fg(Color::Black).bg(Color::Green).bold(),
+            failure: Style::new().fg(Color::White).bg(Color::Red).bold(),
+            custom: Style::new().fg(Color::Black).bg(Color::Cyan).bold(),
+            folded: Style::new().fg(Color::Black).bg(Color::Yellow).bold(),
+            offset: Style::new().bold(),
+            fragment: Style::new().bold(),
+            depth: Vec::new(),
+        }
+    }
+
+    /// Set the indentation colors by depth.
+    ///
+    /// The styles are used cyclically from the outermost parser.
+    pub fn depth(mut self, x: Vec<Style>) -> Self {
+        self.depth = x;
+        self
+    }
+
+    /// Set the default rainbow indentation colors.
+    pub fn rainbow(self) -> Self {
+        let depth = [
+            Color::Fixed(52),
+            Color::Fixed(58),
+            Color::Fixed(22),
+            Color::Fixed(23),
+            Color::Fixed(17),
+            Color::Fixed(53),
+        ];
+        self.depth(depth.iter().map(|x| Style::new().bg(*x)).collect())
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::dark()
+    }
+}
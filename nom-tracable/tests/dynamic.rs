@@ -0,0 +1,46 @@
+#![cfg(feature = "dynamic")]
+
+use nom::character::complete::*;
+use nom::IResult;
+use nom_locate::LocatedSpan;
+use nom_tracable::{set_trace_enabled, statistics, tracable_parser, TracableInfo, TraceRecorder};
+
+type Span<'a> = LocatedSpan<&'a str, TracableInfo>;
+
+#[tracable_parser(dynamic)]
+pub fn term(s: Span) -> IResult<Span, String> {
+    let (s, x) = char('1')(s)?;
+    Ok((s, x.to_string()))
+}
+
+// Traced at runtime only if `stderr` feature is enabled
+#[tracable_parser(dynamic, cfg = "stderr")]
+pub fn stderr_term(s: Span) -> IResult<Span, String> {
+    term(s)
+}
+
+#[test]
+fn dynamic() {
+    let (ret, trace) =
+        TraceRecorder::record(|| term(LocatedSpan::new_extra("1", TracableInfo::new())));
+    assert_eq!("\"1\"", format!("{:?}", ret.unwrap().1));
+    assert!(trace.events().is_empty());
+    assert!(statistics().parser("term").is_none());
+
+    set_trace_enabled(true);
+
+    let (ret, trace) =
+        TraceRecorder::record(|| term(LocatedSpan::new_extra("1", TracableInfo::new())));
+    assert_eq!("\"1\"", format!("{:?}", ret.unwrap().1));
+    assert_eq!(trace.calls("term").count(), 1);
+    assert_eq!(statistics().parser("term").unwrap().calls, 1);
+
+    let (ret, trace) =
+        TraceRecorder::record(|| stderr_term(LocatedSpan::new_extra("1", TracableInfo::new())));
+    assert_eq!("\"1\"", format!("{:?}", ret.unwrap().1));
+    let expected = if cfg!(feature = "stderr") { 1 } else { 0 };
+    assert_eq!(trace.calls("stderr_term").count(), expected);
+    assert_eq!(trace.calls("term").count(), 1);
+
+    set_trace_enabled(false);
+}
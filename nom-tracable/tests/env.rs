@@ -0,0 +1,90 @@
+// `from_env` is tested in a separate binary
+// because modifying environment variables races with the other tests reading them.
+#![cfg(feature = "trace")]
+
+use nom::branch::*;
+use nom::character::complete::*;
+use nom::IResult;
+use nom_locate::LocatedSpan;
+use nom_tracable::{set_trace_enabled, set_trace_sink, tracable_parser, TracableInfo};
+
+type Span<'a> = LocatedSpan<&'a str, TracableInfo>;
+
+#[tracable_parser]
+pub fn expr(s: Span) -> IResult<Span, String> {
+    alt((expr_plus, expr_minus, term))(s)
+}
+
+#[tracable_parser]
+pub fn expr_plus(s: Span) -> IResult<Span, String> {
+    let (s, x) = term(s)?;
+    let (s, y) = char('+')(s)?;
+    let (s, z) = expr(s)?;
+    let ret = format!("{}{}{}", x, y, z);
+    Ok((s, ret))
+}
+
+#[tracable_parser]
+pub fn expr_minus(s: Span) -> IResult<Span, String> {
+    let (s, x) = term(s)?;
+    let (s, y) = char('-')(s)?;
+    let (s, z) = expr(s)?;
+    let ret = format!("{}{}{}", x, y, z);
+    Ok((s, ret))
+}
+
+#[tracable_parser]
+pub fn term(s: Span) -> IResult<Span, String> {
+    term_inner(s)
+}
+
+#[tracable_parser]
+pub fn term_inner(s: Span) -> IResult<Span, String> {
+    let (s, x) = char('1')(s)?;
+    Ok((s, x.to_string()))
+}
+
+#[test]
+fn from_env() {
+    set_trace_enabled(true);
+    let path = std::env::temp_dir().join(format!("nom-tracable-{}.jsonl", std::process::id()));
+    std::env::set_var("NOM_TRACE_FOLD", "term, dummy");
+    std::env::set_var("NOM_TRACE_COLOR", "never");
+    std::env::set_var("NOM_TRACE_MAX_TRACE_DEPTH", "1");
+    std::env::set_var("NOM_TRACE_OUTPUT", &path);
+    let info = TracableInfo::from_env();
+    std::env::remove_var("NOM_TRACE_FOLD");
+    std::env::remove_var("NOM_TRACE_COLOR");
+    std::env::remove_var("NOM_TRACE_MAX_TRACE_DEPTH");
+    std::env::remove_var("NOM_TRACE_OUTPUT");
+
+    assert_eq!(
+        info,
+        TracableInfo::new()
+            .fold("term")
+            .fold("dummy")
+            .color(false)
+            .max_trace_depth(1)
+    );
+
+    let ret = expr(LocatedSpan::new_extra("1", info));
+    assert_eq!("\"1\"", format!("{:?}", ret.unwrap().1));
+    set_trace_sink(nom_tracable::TableSink::default());
+
+    let out = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let names: Vec<_> = out.lines().map(|x| x.split(',').nth(1).unwrap()).collect();
+    assert_eq!(
+        names,
+        vec![
+            r#""name":"expr""#,
+            r#""name":"expr_plus""#,
+            r#""name":"expr_plus""#,
+            r#""name":"expr_minus""#,
+            r#""name":"expr_minus""#,
+            r#""name":"term""#,
+            r#""name":"term""#,
+            r#""name":"expr""#,
+        ]
+    );
+}
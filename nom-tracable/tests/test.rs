@@ -2,7 +2,20 @@ use nom::branch::*;
 use nom::character::complete::*;
 use nom::IResult;
 use nom_locate::LocatedSpan;
-use nom_tracable::{cumulative_histogram, histogram, tracable_parser, TracableInfo};
+use nom_tracable::{
+    cumulative_histogram, histogram, timing_histogram, tracable_parser, TracableInfo,
+};
+#[cfg(feature = "trace")]
+use nom_tracable::{
+    furthest_failure, memo_histogram, reparse, reparse_histogram, statistics, with_trace_sink,
+    ChromeTraceSink, ColorMode, DepthLimit, FailureMode, FailureSink, FoldedStackSink,
+    FoldedWeight, JsonLinesSink, RecursionGuard, TableSink, Theme, TraceEvent, TraceRecorder,
+    TraceSink,
+};
+#[cfg(any(feature = "trace", feature = "guard"))]
+use nom_tracable::{guard_error, GuardError, DEPTH_LIMIT_ERROR};
+#[cfg(feature = "trace")]
+use std::{cell::RefCell, rc::Rc};
 
 type Span<'a> = LocatedSpan<&'a str, TracableInfo>;
 
@@ -40,6 +53,184 @@ pub fn term_inner(s: Span) -> IResult<Span, String> {
     Ok((s, x.to_string()))
 }
 
+#[tracable_parser(name = "number", fold)]
+pub fn folded_term(s: Span) -> IResult<Span, String> {
+    term(s)
+}
+
+#[tracable_parser(silent)]
+pub fn silent_term(s: Span) -> IResult<Span, String> {
+    term(s)
+}
+
+#[tracable_parser(memo)]
+pub fn memo_term(s: Span) -> IResult<Span, String> {
+    term(s)
+}
+
+#[tracable_parser(memo)]
+pub fn memo_expr(s: Span) -> IResult<Span, String> {
+    alt((memo_expr_plus, memo_term))(s)
+}
+
+#[tracable_parser]
+pub fn memo_expr_plus(s: Span) -> IResult<Span, String> {
+    let (s, x) = memo_term(s)?;
+    let (s, y) = char('+')(s)?;
+    let (s, z) = memo_expr(s)?;
+    Ok((s, format!("{}{}{}", x, y, z)))
+}
+
+mod memo_a {
+    use super::*;
+
+    #[tracable_parser(memo)]
+    pub fn digit(s: Span) -> IResult<Span, String> {
+        let (s, _) = char('1')(s)?;
+        Ok((s, String::from("a")))
+    }
+}
+
+mod memo_b {
+    use super::*;
+
+    #[tracable_parser(memo)]
+    pub fn digit(s: Span) -> IResult<Span, String> {
+        let (s, _) = char('1')(s)?;
+        Ok((s, String::from("b")))
+    }
+}
+
+#[tracable_parser(memo)]
+pub fn memo_both(s: Span) -> IResult<Span, String> {
+    let (_, x) = memo_a::digit(s)?;
+    let (s, y) = memo_b::digit(s)?;
+    Ok((s, format!("{}{}", x, y)))
+}
+
+// Input whose `format` contains the offset without implementing `Tracable::offset`
+#[derive(Clone, Copy, Debug)]
+pub struct Plain<'a> {
+    fragment: &'a str,
+    offset: usize,
+    info: TracableInfo,
+}
+
+impl<'a> nom_tracable::HasTracableInfo for Plain<'a> {
+    fn get_tracable_info(&self) -> TracableInfo {
+        self.info
+    }
+
+    fn set_tracable_info(self, info: TracableInfo) -> Self {
+        Plain { info, ..self }
+    }
+}
+
+impl<'a> nom_tracable::Tracable for Plain<'a> {
+    #[cfg(feature = "trace")]
+    fn inc_depth(self) -> Self {
+        let info = self.info.depth(self.info.depth + 1);
+        Plain { info, ..self }
+    }
+
+    #[cfg(not(feature = "trace"))]
+    fn inc_depth(self) -> Self {
+        self
+    }
+
+    #[cfg(feature = "trace")]
+    fn dec_depth(self) -> Self {
+        let info = self.info.depth(self.info.depth - 1);
+        Plain { info, ..self }
+    }
+
+    #[cfg(not(feature = "trace"))]
+    fn dec_depth(self) -> Self {
+        self
+    }
+
+    fn format(&self) -> String {
+        format!("{} @ {}", self.fragment, self.offset)
+    }
+
+    fn header(&self) -> String {
+        String::from("fragment @ offset")
+    }
+}
+
+impl<'a> nom::Slice<std::ops::RangeFrom<usize>> for Plain<'a> {
+    fn slice(&self, range: std::ops::RangeFrom<usize>) -> Self {
+        Plain {
+            fragment: &self.fragment[range.start..],
+            offset: self.offset + range.start,
+            ..*self
+        }
+    }
+}
+
+#[tracable_parser(memo)]
+pub fn plain_char(s: Plain) -> IResult<Plain, char> {
+    match s.fragment.chars().next() {
+        Some(x) => Ok((nom::Slice::slice(&s, x.len_utf8()..), x)),
+        None => Err(nom::Err::Error(nom::error::Error::new(
+            s,
+            nom::error::ErrorKind::Eof,
+        ))),
+    }
+}
+
+#[tracable_parser(memo)]
+pub fn plain_pair(s: Plain) -> IResult<Plain, String> {
+    let (s, x) = plain_char(s)?;
+    let (s, y) = plain_char(s)?;
+    Ok((s, format!("{}{}", x, y)))
+}
+
+// Left recursive parsers terminate only with `RecursionGuard`
+#[tracable_parser(guard)]
+pub fn lr_expr(s: Span) -> IResult<Span, String> {
+    alt((lr_expr_plus, term))(s)
+}
+
+#[tracable_parser(guard)]
+pub fn lr_expr_plus(s: Span) -> IResult<Span, String> {
+    let (s, x) = lr_expr(s)?;
+    let (s, y) = char('+')(s)?;
+    let (s, z) = term(s)?;
+    Ok((s, format!("{}{}{}", x, y, z)))
+}
+
+#[tracable_parser(guard)]
+pub fn paren(s: Span) -> IResult<Span, String> {
+    alt((paren_inner, term))(s)
+}
+
+#[tracable_parser(guard)]
+pub fn paren_inner(s: Span) -> IResult<Span, String> {
+    let (s, _) = char('(')(s)?;
+    let (s, x) = paren(s)?;
+    let (s, _) = char(')')(s)?;
+    Ok((s, x))
+}
+
+// Error type without `nom::error::ParseError` can be used without `guard`
+#[derive(Debug, PartialEq)]
+pub struct CustomError;
+
+#[tracable_parser]
+pub fn custom_error(s: Span) -> IResult<Span, String, CustomError> {
+    match s.fragment().chars().next() {
+        Some('1') => Ok((s, String::from("1"))),
+        _ => Err(nom::Err::Error(CustomError)),
+    }
+}
+
+// Traced only if `stderr` feature is enabled
+#[tracable_parser(cfg = "stderr")]
+pub fn stderr_term(s: Span) -> IResult<Span, String> {
+    term(s)
+}
+
 #[test]
 fn test() {
     let ret = expr(LocatedSpan::new_extra("1", TracableInfo::new()));
@@ -68,4 +259,858 @@ fn test() {
 
     histogram();
     cumulative_histogram();
+    timing_histogram();
+}
+
+#[cfg(feature = "trace")]
+#[derive(Default)]
+struct LineSink {
+    lines: Vec<String>,
+}
+
+#[cfg(feature = "trace")]
+impl TraceSink for LineSink {
+    fn event(&mut self, _info: &TracableInfo, event: &TraceEvent) {
+        let line = match event {
+            TraceEvent::Enter { name, count, .. } => format!("{} -> {}", count, name),
+            TraceEvent::ExitOk {
+                name,
+                count,
+                offset,
+                consumed,
+                ..
+            } => format!(
+                "{} <- {} ok {}+{}",
+                count,
+                name,
+                offset.unwrap(),
+                consumed.unwrap()
+            ),
+            TraceEvent::ExitErr { name, count, .. } => format!("{} <- {} err", count, name),
+            TraceEvent::Custom { name, message, .. } => format!("{}: {}", name, message),
+        };
+        self.lines.push(line);
+    }
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn tracable_default() {
+    use nom_tracable::{HasTracableInfo, Tracable};
+
+    // Custom input implementing only the required methods
+    struct Custom(usize, TracableInfo);
+
+    impl HasTracableInfo for Custom {
+        fn get_tracable_info(&self) -> TracableInfo {
+            self.1
+        }
+
+        fn set_tracable_info(self, info: TracableInfo) -> Self {
+            Custom(self.0, info)
+        }
+    }
+
+    impl Tracable for Custom {
+        fn inc_depth(self) -> Self {
+            self
+        }
+
+        fn dec_depth(self) -> Self {
+            self
+        }
+
+        fn format(&self) -> String {
+            format!("{:<8} : {}", self.0, "abc")
+        }
+
+        fn header(&self) -> String {
+            format!("{:<8} : {}", "offset", "fragment")
+        }
+    }
+
+    let input = Custom(12, TracableInfo::new());
+    assert_eq!(input.offset(), None);
+    assert_eq!(input.display_fragment(), "abc");
+    assert_eq!(input.location(), None);
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn sink() {
+    let sink = Rc::new(RefCell::new(LineSink::default()));
+    let ret = with_trace_sink(sink.clone(), || {
+        expr(LocatedSpan::new_extra(
+            "1",
+            TracableInfo::new().fold("term"),
+        ))
+    });
+    assert_eq!("\"1\"", format!("{:?}", ret.unwrap().1));
+    assert_eq!(
+        sink.borrow().lines,
+        vec![
+            "1 -> expr",
+            "2 -> expr_plus",
+            "3 -> term",
+            "1 <- term ok 0+1",
+            "2 <- expr_plus err",
+            "4 -> expr_minus",
+            "5 -> term",
+            "3 <- term ok 0+1",
+            "4 <- expr_minus err",
+            "6 -> term",
+            "5 <- term ok 0+1",
+            "6 <- expr ok 0+1",
+        ]
+    );
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn recorder() {
+    let (ret, trace) =
+        TraceRecorder::record(|| expr(LocatedSpan::new_extra("1+1-", TracableInfo::new())));
+    assert_eq!("\"1+1\"", format!("{:?}", ret.unwrap().1));
+
+    assert_eq!(trace.calls("expr").count(), 3);
+    assert_eq!(trace.calls("term_inner").count(), 7);
+    assert!(trace.entered_at("expr", 2));
+    assert!(trace.succeeded_at("term", 2));
+    assert!(trace.failed_at("term", 4));
+    assert_eq!(
+        trace.failures_at(4),
+        vec![
+            "term_inner",
+            "term",
+            "expr_plus",
+            "term_inner",
+            "term",
+            "expr_minus",
+            "term_inner",
+            "term",
+            "expr"
+        ]
+    );
+    assert_eq!(trace.max_depth(), 7);
+}
+
+#[cfg(feature = "trace")]
+#[derive(Clone, Default)]
+struct SharedBuf(Rc<RefCell<Vec<u8>>>);
+
+#[cfg(feature = "trace")]
+impl std::io::Write for SharedBuf {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn json_lines() {
+    let buf = SharedBuf::default();
+    let _ = with_trace_sink(JsonLinesSink::new(buf.clone()), || {
+        term(LocatedSpan::new_extra(
+            "1\"",
+            TracableInfo::new().fold("term"),
+        ))
+    });
+    let out = String::from_utf8(buf.0.borrow().clone()).unwrap();
+    assert_eq!(
+        out,
+        concat!(
+            r#"{"event":"enter","name":"term","depth":0,"forward":1,"offset":0,"line":1,"column":1,"fragment":"1\""}"#,
+            "\n",
+            r#"{"event":"exit","name":"term","depth":0,"backward":1,"offset":0,"line":1,"column":1,"result":"ok","consumed":1,"fragment":"\""}"#,
+            "\n",
+        )
+    );
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn table_color() {
+    let table = |info: TracableInfo| {
+        let buf = SharedBuf::default();
+        let _ = with_trace_sink(TableSink::new(buf.clone()), || {
+            term(LocatedSpan::new_extra("1", info.parser_width(16)))
+        });
+        let out = String::from_utf8(buf.0.borrow().clone()).unwrap();
+        out
+    };
+
+    let out = table(TracableInfo::new());
+    assert!(!out.contains('\u{001b}'));
+    let columns: Vec<_> = out.lines().skip(1).map(|x| x.rfind(" : ")).collect();
+    assert!(columns.iter().all(|x| x.is_some() && *x == columns[0]));
+
+    let out = table(TracableInfo::new().color(ColorMode::Always));
+    assert!(out.contains("\u{001b}[1;37m-> term\u{001b}[0m          : 0"));
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn table_theme() {
+    let buf = SharedBuf::default();
+    let sink = TableSink::new(buf.clone()).theme(Theme::light().rainbow());
+    let _ = with_trace_sink(sink, || {
+        term(LocatedSpan::new_extra(
+            "1",
+            TracableInfo::new().color(true).parser_width(16),
+        ))
+    });
+    let out = String::from_utf8(buf.0.borrow().clone()).unwrap();
+
+    assert!(out.contains("\u{001b}[1;30m-> term\u{001b}[0m"));
+    assert!(out.contains("\u{001b}[48;5;52m \u{001b}[0m\u{001b}[1;30m-> term_inner\u{001b}[0m"));
+    assert!(out.contains("\u{001b}[1;38;5;28m<- term\u{001b}[0m"));
+    assert!(out.contains("\u{001b}[38;5;242m0       \u{001b}[0m : 1"));
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn chrome_trace() {
+    let buf = SharedBuf::default();
+    let _ = with_trace_sink(ChromeTraceSink::new(buf.clone()), || {
+        term(LocatedSpan::new_extra("1", TracableInfo::new()))
+    });
+    let out = String::from_utf8(buf.0.borrow().clone()).unwrap();
+    let lines: Vec<_> = out.lines().collect();
+    assert_eq!(lines.len(), 6);
+    assert_eq!(lines[0], "[");
+    assert!(lines[1].starts_with(r#"{"name":"term","cat":"parser","ph":"B","ts":"#));
+    assert!(lines[2].starts_with(r#"{"name":"term_inner","cat":"parser","ph":"B","ts":"#));
+    assert!(lines[3].starts_with(r#"{"name":"term_inner","cat":"parser","ph":"E","ts":"#));
+    assert!(lines[4].starts_with(r#"{"name":"term","cat":"parser","ph":"E","ts":"#));
+    assert!(lines[4].ends_with(r#""args":{"result":"ok","consumed":1}}"#));
+    assert_eq!(lines[5], "]");
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn folded_stack() {
+    let buf = SharedBuf::default();
+    let _ = with_trace_sink(
+        FoldedStackSink::new(buf.clone(), FoldedWeight::Calls),
+        || expr(LocatedSpan::new_extra("1+1", TracableInfo::new())),
+    );
+    let out = String::from_utf8(buf.0.borrow().clone()).unwrap();
+    assert_eq!(
+        out,
+        concat!(
+            "expr 1\n",
+            "expr;expr_plus 1\n",
+            "expr;expr_plus;expr 1\n",
+            "expr;expr_plus;expr;expr_minus 1\n",
+            "expr;expr_plus;expr;expr_minus;term 1\n",
+            "expr;expr_plus;expr;expr_minus;term;term_inner 1\n",
+            "expr;expr_plus;expr;expr_plus 1\n",
+            "expr;expr_plus;expr;expr_plus;term 1\n",
+            "expr;expr_plus;expr;expr_plus;term;term_inner 1\n",
+            "expr;expr_plus;expr;term 1\n",
+            "expr;expr_plus;expr;term;term_inner 1\n",
+            "expr;expr_plus;term 1\n",
+            "expr;expr_plus;term;term_inner 1\n",
+        )
+    );
+
+    let buf = SharedBuf::default();
+    let _ = with_trace_sink(
+        FoldedStackSink::new(buf.clone(), FoldedWeight::Bytes),
+        || expr(LocatedSpan::new_extra("1+1", TracableInfo::new())),
+    );
+    let out = String::from_utf8(buf.0.borrow().clone()).unwrap();
+    assert!(out.contains("expr;expr_plus 1\n"));
+    assert!(out.contains("expr;expr_plus;term;term_inner 1\n"));
+    assert!(out.contains("expr;expr_plus;term 0\n"));
+}
+
+#[cfg(feature = "trace")]
+#[derive(Default)]
+struct TimingSink {
+    timing: Vec<(String, std::time::Duration, std::time::Duration)>,
+    delay: std::time::Duration,
+}
+
+#[cfg(feature = "trace")]
+impl TraceSink for TimingSink {
+    fn event(&mut self, _info: &TracableInfo, _event: &TraceEvent) {
+        std::thread::sleep(self.delay);
+    }
+
+    fn timing_histogram(&mut self, histogram: &[(&str, std::time::Duration, std::time::Duration)]) {
+        self.timing = histogram
+            .iter()
+            .map(|(p, t, s)| (p.to_string(), *t, *s))
+            .collect();
+    }
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn timing() {
+    let sink = Rc::new(RefCell::new(TimingSink::default()));
+    with_trace_sink(sink.clone(), || {
+        let _ = expr(LocatedSpan::new_extra("1+1-1", TracableInfo::new()));
+        timing_histogram();
+    });
+
+    let timing = &sink.borrow().timing;
+    let mut names: Vec<_> = timing.iter().map(|x| x.0.as_str()).collect();
+    names.sort();
+    assert_eq!(
+        names,
+        vec!["expr", "expr_minus", "expr_plus", "term", "term_inner"]
+    );
+    for (_, t, s) in timing {
+        assert!(t >= s);
+    }
+    let outer = timing.iter().find(|x| x.0 == "expr").unwrap();
+    assert!(timing.iter().all(|x| x.1 <= outer.1));
+
+    // The time spent by the sink is not counted
+    let delay = std::time::Duration::from_millis(10);
+    let sink = Rc::new(RefCell::new(TimingSink {
+        delay,
+        ..Default::default()
+    }));
+    with_trace_sink(sink.clone(), || {
+        let _ = expr(LocatedSpan::new_extra("1", TracableInfo::new()));
+        timing_histogram();
+    });
+    let timing = &sink.borrow().timing;
+    let outer = timing.iter().find(|x| x.0 == "expr").unwrap();
+    assert!(outer.1 < delay);
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn parse_statistics() {
+    let _ = expr(LocatedSpan::new_extra("1+1", TracableInfo::new()));
+    let stats = statistics();
+
+    assert_eq!(stats.max_depth, 5);
+    assert_eq!(stats.total_calls(), 13);
+
+    let expr = stats.parser("expr").unwrap();
+    assert_eq!(expr.calls, 2);
+    assert_eq!(expr.cumulative_calls, 13 + 9);
+    assert_eq!((expr.success, expr.failure), (2, 0));
+
+    let term = stats.parser("term").unwrap();
+    assert_eq!(term.calls, 4);
+    assert_eq!((term.success, term.failure), (4, 0));
+
+    let expr_plus = stats.parser("expr_plus").unwrap();
+    assert_eq!((expr_plus.success, expr_plus.failure), (1, 1));
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn trace_limits() {
+    let record =
+        |info: TracableInfo| TraceRecorder::record(|| expr(LocatedSpan::new_extra("1+1-", info))).1;
+
+    let trace = record(TracableInfo::new().min_trace_depth(2).max_trace_depth(3));
+    assert!(!trace.events().is_empty());
+    assert!(trace.events().iter().all(|x| (2..=3).contains(&x.depth())));
+    assert_eq!(statistics().parser("expr").unwrap().calls, 3);
+
+    let trace = record(TracableInfo::new().offset_window(2..3));
+    assert!(!trace.events().is_empty());
+    assert!(trace.events().iter().all(|x| x.offset() == Some(2)));
+
+    // The exits of displayed parsers are displayed after the limit
+    let trace = record(TracableInfo::new().max_trace_lines(5));
+    let enter = |x: &&TraceEvent| matches!(x, TraceEvent::Enter { .. });
+    assert_eq!(trace.events().iter().filter(enter).count(), 4);
+    assert_eq!(trace.events().len(), 8);
+    assert_eq!(statistics().parser("term_inner").unwrap().calls, 7);
+
+    // Stack-based sinks receive balanced events
+    let buf = SharedBuf::default();
+    let _ = with_trace_sink(
+        FoldedStackSink::new(buf.clone(), FoldedWeight::Calls),
+        || {
+            expr(LocatedSpan::new_extra(
+                "1+1-",
+                TracableInfo::new().max_trace_lines(3),
+            ))
+        },
+    );
+    let out = String::from_utf8(buf.0.borrow().clone()).unwrap();
+    assert_eq!(out, "expr 1\nexpr;expr_plus 1\nexpr;expr_plus;term 1\n");
+
+    let recorder = Rc::new(RefCell::new(TraceRecorder::new()));
+    let sink = FailureSink::new(recorder.clone(), FailureMode::All);
+    let _ = with_trace_sink(sink, || {
+        expr(LocatedSpan::new_extra(
+            "2",
+            TracableInfo::new().max_trace_lines(3),
+        ))
+    });
+    assert_eq!(recorder.borrow().events().len(), 6);
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn name_filter() {
+    let record = |info: TracableInfo| {
+        let (_, trace) = TraceRecorder::record(|| expr(LocatedSpan::new_extra("1+1", info)));
+        let mut names: Vec<_> = trace
+            .events()
+            .iter()
+            .map(|x| x.name().to_string())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    };
+
+    let info = TracableInfo::new().include("expr_*|t?rm");
+    assert_eq!(record(info), vec!["expr_minus", "expr_plus", "term"]);
+
+    // Unlike fold, the children of excluded parsers are displayed
+    let info = TracableInfo::new().exclude("expr*").exclude("term");
+    assert_eq!(record(info), vec!["term_inner"]);
+
+    let info = TracableInfo::new().include("*").exclude("*_*");
+    assert_eq!(record(info), vec!["expr", "term"]);
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn failure_sink() {
+    let record = |mode: FailureMode| {
+        let recorder = Rc::new(RefCell::new(TraceRecorder::new()));
+        let sink = FailureSink::new(recorder.clone(), mode);
+        let _ = with_trace_sink(sink, || {
+            expr(LocatedSpan::new_extra("1+1-", TracableInfo::new()))
+        });
+        let trace = recorder.borrow().clone();
+        trace
+    };
+
+    let trace = record(FailureMode::Furthest);
+    let path: Vec<_> = trace
+        .events()
+        .iter()
+        .filter(|x| matches!(x, TraceEvent::Enter { .. }))
+        .map(|x| (x.name(), x.offset().unwrap()))
+        .collect();
+    assert_eq!(
+        path,
+        vec![
+            ("expr", 0),
+            ("expr_plus", 0),
+            ("expr", 2),
+            ("expr_minus", 2),
+            ("expr", 4),
+            ("expr_plus", 4),
+            ("term", 4),
+            ("term_inner", 4),
+        ]
+    );
+    assert_eq!(trace.events().len(), path.len() * 2);
+
+    let (_, full) =
+        TraceRecorder::record(|| expr(LocatedSpan::new_extra("1+1-", TracableInfo::new())));
+    let failures = |trace: &TraceRecorder| {
+        trace
+            .events()
+            .iter()
+            .filter(|x| matches!(x, TraceEvent::ExitErr { .. }))
+            .count()
+    };
+    let trace = record(FailureMode::All);
+    assert!(trace.events().len() < full.events().len());
+    assert_eq!(failures(&trace), failures(&full));
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn failure_report() {
+    let _ = expr(LocatedSpan::new_extra("1+1-", TracableInfo::new()));
+    let report = furthest_failure().unwrap();
+    assert_eq!(report.offset, 4);
+    assert_eq!(
+        report.to_string(),
+        "at line 1, col 5: expected one of expr_plus, expr_minus, term"
+    );
+
+    // The offset is the position at which the failed parser was entered
+    let _ = expr(LocatedSpan::new_extra("1", TracableInfo::new()));
+    let report = furthest_failure().unwrap();
+    assert_eq!(report.offset, 0);
+    assert_eq!(report.expected, vec!["expr_plus", "expr_minus"]);
+
+    // The parser wrapping only one failed parser replaces it
+    let _ = term(LocatedSpan::new_extra("2", TracableInfo::new()));
+    let report = furthest_failure().unwrap();
+    assert_eq!(report.expected, vec!["term"]);
+
+    let _ = term(LocatedSpan::new_extra("1", TracableInfo::new()));
+    assert_eq!(furthest_failure(), None);
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn snippet() {
+    let info = TracableInfo::new().snippet(true).parser_width(16);
+    let buf = SharedBuf::default();
+    let _ = with_trace_sink(TableSink::new(buf.clone()), || {
+        expr(LocatedSpan::new_extra("1+1-\n1", info))
+    });
+    let out = String::from_utf8(buf.0.borrow().clone()).unwrap();
+    assert!(out.contains(concat!(
+        "           5          :    <- expr_plus  \n",
+        "                      :       1 | 1+1-\n",
+        "                      :         |   ^\n",
+    )));
+
+    let report = furthest_failure().unwrap();
+    assert_eq!(
+        report.to_string(),
+        concat!(
+            "at line 1, col 5: expected one of expr_plus, expr_minus, term\n",
+            "1 | 1+1-\n",
+            "  |     ^"
+        )
+    );
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn reparse_detector() {
+    let _ = expr(LocatedSpan::new_extra("1+1", TracableInfo::new()));
+    assert!(reparse().is_empty());
+
+    let _ = expr(LocatedSpan::new_extra(
+        "1+1",
+        TracableInfo::new().reparse(true),
+    ));
+    let reparse = reparse();
+    reparse_histogram();
+
+    let names: Vec<_> = reparse.iter().map(|x| x.name.as_str()).collect();
+    assert_eq!(names, vec!["term", "term_inner"]);
+
+    // `term` at offset 2 is parsed by all alternatives of `expr`
+    let term = &reparse[0];
+    assert_eq!(
+        (term.redundant_calls, term.offsets, term.max_calls),
+        (2, 1, 3)
+    );
+    let callers: Vec<_> = term.callers.iter().map(|(x, y)| (x.as_str(), *y)).collect();
+    assert_eq!(
+        callers,
+        vec![("expr", 1), ("expr_minus", 1), ("expr_plus", 1)]
+    );
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn fold_many() {
+    // Parsers traced before `fold` are folded too
+    let _ = expr(LocatedSpan::new_extra("1", TracableInfo::new()));
+
+    let mut info = TracableInfo::new();
+    for i in 0..100 {
+        info = info.fold(&format!("dummy{}", i));
+    }
+    let info = info.fold("term");
+
+    // The order of `fold` is not significant
+    let mut reversed = TracableInfo::new().fold("term");
+    for i in (0..100).rev() {
+        reversed = reversed.fold(&format!("dummy{}", i));
+    }
+    assert_eq!(info, reversed);
+
+    let (_, trace) = TraceRecorder::record(|| expr(LocatedSpan::new_extra("1", info)));
+    assert_eq!(trace.calls("term").count(), 3);
+    assert_eq!(trace.calls("term_inner").count(), 0);
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn other_thread() {
+    // `TracableInfo` built in a thread can be used in another thread
+    let info = TracableInfo::new().fold("term");
+    let trace = std::thread::spawn(move || {
+        let (ret, trace) = TraceRecorder::record(|| expr(LocatedSpan::new_extra("1", info)));
+        assert_eq!("\"1\"", format!("{:?}", ret.unwrap().1));
+        trace
+    })
+    .join()
+    .unwrap();
+    assert_eq!(trace.calls("term").count(), 3);
+    assert_eq!(trace.calls("term_inner").count(), 0);
+
+    let info = TracableInfo::new()
+        .min_trace_depth(1)
+        .offset_window(0..1)
+        .max_trace_lines(100)
+        .exclude("term_inner");
+    let trace = std::thread::spawn(move || {
+        TraceRecorder::record(|| expr(LocatedSpan::new_extra("1", info))).1
+    })
+    .join()
+    .unwrap();
+    assert_eq!(trace.calls("expr").count(), 0);
+    assert_eq!(trace.calls("term").count(), 3);
+    assert_eq!(trace.calls("term_inner").count(), 0);
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn parser_options() {
+    let (ret, trace) =
+        TraceRecorder::record(|| folded_term(LocatedSpan::new_extra("1", TracableInfo::new())));
+    assert_eq!("\"1\"", format!("{:?}", ret.unwrap().1));
+    assert_eq!(trace.calls("folded_term").count(), 0);
+    assert_eq!(trace.calls("number").count(), 1);
+    assert_eq!(trace.calls("term").count(), 0);
+    assert_eq!(statistics().parser("term").unwrap().calls, 1);
+
+    let (ret, trace) =
+        TraceRecorder::record(|| silent_term(LocatedSpan::new_extra("1", TracableInfo::new())));
+    assert_eq!("\"1\"", format!("{:?}", ret.unwrap().1));
+    assert_eq!(trace.calls("silent_term").count(), 0);
+    assert_eq!(trace.calls("term").count(), 1);
+    assert_eq!(statistics().parser("silent_term").unwrap().calls, 1);
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn parser_memo() {
+    let (ret, trace) =
+        TraceRecorder::record(|| memo_expr(LocatedSpan::new_extra("1+1", TracableInfo::new())));
+    let (s, x) = ret.unwrap();
+    assert_eq!((x.as_str(), *s.fragment()), ("1+1", ""));
+
+    // `memo_term` at offset 2 is memoized by `memo_expr_plus` and reused by `memo_expr`
+    assert_eq!(trace.calls("memo_term").count(), 3);
+    assert_eq!(trace.calls("term").count(), 2);
+    let memo_term = statistics().parser("memo_term").unwrap().clone();
+    assert_eq!((memo_term.memo_hits, memo_term.memo_misses), (1, 2));
+
+    // Failures are re-parsed to return the original error
+    let (ret, trace) =
+        TraceRecorder::record(|| memo_expr(LocatedSpan::new_extra("2", TracableInfo::new())));
+    match ret {
+        Err(nom::Err::Error(x)) => assert_eq!(x.code, nom::error::ErrorKind::Char),
+        x => panic!("unexpected result: {:?}", x),
+    }
+    assert_eq!(trace.calls("term").count(), 2);
+    assert!(trace.failed_at("memo_term", 0));
+    memo_histogram();
+
+    // Parsers with the same name in different modules are memoized separately
+    let ret = memo_both(LocatedSpan::new_extra("1", TracableInfo::new()));
+    assert_eq!("\"ab\"", format!("{:?}", ret.unwrap().1));
+}
+
+#[test]
+fn memo_input() {
+    // The memo works without trace
+    let ret = memo_expr(LocatedSpan::new_extra("1+1", TracableInfo::new()));
+    assert_eq!("\"1+1\"", format!("{:?}", ret.unwrap().1));
+
+    // The input without offset is not memoized
+    let input = Plain {
+        fragment: "ab",
+        offset: 0,
+        info: TracableInfo::new(),
+    };
+    let (s, x) = plain_pair(input).unwrap();
+    assert_eq!((x.as_str(), s.fragment, s.offset), ("ab", "", 2));
+}
+
+#[test]
+fn error_type() {
+    let ret = custom_error(LocatedSpan::new_extra("2", TracableInfo::new()));
+    assert_eq!(ret.unwrap_err(), nom::Err::Error(CustomError));
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn recursion_guard() {
+    let info = TracableInfo::new().recursion_guard(RecursionGuard::Error);
+    let (ret, trace) = TraceRecorder::record(|| lr_expr(LocatedSpan::new_extra("1+1", info)));
+    let (s, x) = ret.unwrap();
+    assert_eq!((x.as_str(), *s.fragment()), ("1", "+1"));
+    assert!(trace.failed_at("lr_expr", 0));
+    assert!(trace.events().iter().any(|x| matches!(
+        x,
+        TraceEvent::Custom { message, .. } if message == "left recursion: lr_expr -> lr_expr_plus -> lr_expr at offset 0"
+    )));
+
+    let info = TracableInfo::new().recursion_guard(RecursionGuard::Panic);
+    let ret = std::panic::catch_unwind(|| {
+        with_trace_sink(TraceRecorder::new(), || {
+            lr_expr(LocatedSpan::new_extra("1+1", info))
+        })
+    });
+    let message = *ret.unwrap_err().downcast::<String>().unwrap();
+    assert_eq!(
+        message,
+        "left recursion: lr_expr -> lr_expr_plus -> lr_expr at offset 0"
+    );
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn with_trace_sink_panic() {
+    let info = TracableInfo::new().recursion_guard(RecursionGuard::Panic);
+    let (_, trace) = TraceRecorder::record(|| {
+        let ret = std::panic::catch_unwind(|| {
+            with_trace_sink(TraceRecorder::new(), || {
+                lr_expr(LocatedSpan::new_extra("1+1", info))
+            })
+        });
+        assert!(ret.is_err());
+        term(LocatedSpan::new_extra("1", TracableInfo::new()))
+    });
+    assert_eq!(trace.calls("term").count(), 1);
+    assert_eq!(trace.calls("lr_expr").count(), 0);
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn depth_limit() {
+    let info = TracableInfo::new().max_depth(10);
+    let ret = paren(LocatedSpan::new_extra("((1))", info));
+    assert_eq!("\"1\"", format!("{:?}", ret.unwrap().1));
+
+    let input = format!("{}1{}", "(".repeat(10000), ")".repeat(10000));
+    let (ret, trace) = TraceRecorder::record(|| paren(LocatedSpan::new_extra(&input, info)));
+    match ret {
+        Err(nom::Err::Failure(x)) => {
+            assert_eq!(x.code, DEPTH_LIMIT_ERROR);
+            assert_eq!(x.input.location_offset(), 5);
+        }
+        x => panic!("unexpected result: {:?}", x),
+    }
+    assert_eq!(trace.calls("paren").count(), 6);
+    assert!(trace.events().iter().any(|x| matches!(
+        x,
+        TraceEvent::Custom { message, .. } if message == "max depth 10 exceeded"
+    )));
+    assert_eq!(
+        guard_error(),
+        Some(GuardError::DepthLimit {
+            parser: String::from("paren"),
+            max_depth: 10
+        })
+    );
+
+    // The error is printed with the call stack instead of panic
+    let info = info.max_depth(3).depth_limit(DepthLimit::Print);
+    let ret = with_trace_sink(TraceRecorder::new(), || {
+        paren(LocatedSpan::new_extra("((1))", info))
+    });
+    match ret {
+        Err(nom::Err::Failure(x)) => assert_eq!(x.code, DEPTH_LIMIT_ERROR),
+        x => panic!("unexpected result: {:?}", x),
+    }
+    assert_eq!(
+        guard_error(),
+        Some(GuardError::DepthLimit {
+            parser: String::from("paren_inner"),
+            max_depth: 3
+        })
+    );
+}
+
+#[cfg(not(any(feature = "trace", feature = "guard")))]
+#[test]
+fn zero_sized() {
+    assert_eq!(std::mem::size_of::<TracableInfo>(), 0);
+}
+
+#[cfg(any(feature = "trace", feature = "guard"))]
+#[test]
+fn depth_limit_without_trace() {
+    let info = TracableInfo::new().max_depth(100);
+    let ret = paren(LocatedSpan::new_extra("((1))", info));
+    assert_eq!("\"1\"", format!("{:?}", ret.unwrap().1));
+    assert_eq!(guard_error(), None);
+
+    let input = format!("{}1{}", "(".repeat(100000), ")".repeat(100000));
+    match paren(LocatedSpan::new_extra(&input, info)) {
+        Err(nom::Err::Failure(x)) => {
+            assert_eq!(x.code, DEPTH_LIMIT_ERROR);
+            assert_eq!(x.input.location_offset(), 50);
+        }
+        x => panic!("unexpected result: {:?}", x),
+    }
+    assert_eq!(
+        guard_error(),
+        Some(GuardError::DepthLimit {
+            parser: String::from("paren"),
+            max_depth: 100
+        })
+    );
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn call_graph() {
+    let info = TracableInfo::new().forward(false).backward(false);
+    let ret = expr(LocatedSpan::new_extra("1-1", info));
+    assert_eq!("\"1-1\"", format!("{:?}", ret.unwrap().1));
+
+    let graph = nom_tracable::call_graph();
+    assert_eq!(graph.roots.iter().collect::<Vec<_>>(), ["expr"]);
+    let edge = graph.edge("expr", "expr_plus").unwrap();
+    assert_eq!((edge.calls, edge.success), (2, 0));
+    let edge = graph.edge("expr", "expr_minus").unwrap();
+    assert_eq!(
+        (edge.calls, edge.success, edge.success_ratio()),
+        (2, 1, 0.5)
+    );
+    assert_eq!(graph.callers("term").count(), 3);
+    let dot = graph.to_dot();
+    assert!(dot.starts_with("digraph parsers {\n    \"expr\" [shape=doublecircle];\n"));
+    assert!(dot.contains("    \"expr\" -> \"expr_minus\" [label=\"2 calls, 50% ok\"];\n"));
+    let mermaid = graph.to_mermaid();
+    assert!(mermaid.starts_with("flowchart TD\n    n0(\"expr\")\n    n1[\"expr_minus\"]\n"));
+    assert!(mermaid.contains("    n3 -->|\"5 calls, 100% ok\"| n4\n"));
+}
+
+#[test]
+fn call_graph_escape() {
+    let graph = nom_tracable::CallGraph {
+        roots: vec![String::from("end")].into_iter().collect(),
+        edges: vec![nom_tracable::CallEdge {
+            caller: String::from("end"),
+            callee: String::from("say \"hi\" \\ #1"),
+            calls: 1,
+            success: 1,
+        }],
+    };
+    assert_eq!(
+        graph.to_dot(),
+        "digraph parsers {\n    \"end\" [shape=doublecircle];\n    \"say \\\"hi\\\" \\\\ #1\" [shape=ellipse];\n    \"end\" -> \"say \\\"hi\\\" \\\\ #1\" [label=\"1 calls, 100% ok\"];\n}\n"
+    );
+    assert_eq!(
+        graph.to_mermaid(),
+        "flowchart TD\n    n0(\"end\")\n    n1[\"say #quot;hi#quot; \\ #35;1\"]\n    n0 -->|\"1 calls, 100% ok\"| n1\n"
+    );
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn parser_cfg() {
+    let (ret, trace) =
+        TraceRecorder::record(|| stderr_term(LocatedSpan::new_extra("1", TracableInfo::new())));
+    assert_eq!("\"1\"", format!("{:?}", ret.unwrap().1));
+    let expected = if cfg!(feature = "stderr") { 1 } else { 0 };
+    assert_eq!(trace.calls("stderr_term").count(), expected);
+    assert_eq!(trace.calls("term").count(), 1);
 }